@@ -0,0 +1,248 @@
+//! Native frame renderer: the same engine the Bun workers drive through wasm,
+//! without the JS runtime.
+//!
+//! ```text
+//! amethyst-render scene.json -w 1920 -h 1080 -m manifest.json -o out/frame_{frame}.png
+//! amethyst-render frames.ndjson -w 1920 -h 1080 --raw | ffmpeg -f rawvideo -pix_fmt rgba -s 1920x1080 -i - out.mp4
+//! ```
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use amethyst_core::{EngineCore, StyleConfig};
+use serde::Deserialize;
+use tiny_skia::PixmapRef;
+
+const USAGE: &str = "\
+Usage: amethyst-render [OPTIONS] <SCENE>
+
+Arguments:
+  <SCENE>                 Scene JSON file, or NDJSON with one scene per line (`-` reads stdin)
+
+Options:
+  -w, --width <PX>        Frame width [default: 1920]
+  -h, --height <PX>       Frame height [default: 1080]
+  -m, --manifest <FILE>   JSON file with `fonts`, `assets` and `luts` (.cube) maps of
+                          name -> path, and a `styles` map of class name -> style
+  -o, --out <PATTERN>     PNG output path, `{frame}` becomes the zero-padded frame number
+                          [default: frame_{frame}.png]
+      --ndjson            Treat SCENE as NDJSON even without a .ndjson/.jsonl extension
+      --raw               Write raw RGBA frames, with straight alpha like PNG, to stdout
+                          instead of PNG files
+      --premultiplied     With --raw, keep alpha premultiplied, as the engine renders it
+      --layout            Write each frame's node boxes to stdout as a JSON line instead of rendering
+      --help              Print this message";
+
+#[derive(Deserialize, Default)]
+struct Manifest {
+    #[serde(default)]
+    fonts: HashMap<String, PathBuf>,
+    #[serde(default)]
+    assets: HashMap<String, PathBuf>,
+    #[serde(default)]
+    luts: HashMap<String, PathBuf>,
+    #[serde(default)]
+    styles: HashMap<String, StyleConfig>,
+}
+
+struct Options {
+    scene: String,
+    width: u32,
+    height: u32,
+    manifest: Option<PathBuf>,
+    out: String,
+    ndjson: bool,
+    raw: bool,
+    premultiplied: bool,
+    layout: bool,
+}
+
+/// Returns `Ok(None)` when `--help` was requested.
+fn parse_args() -> Result<Option<Options>, String> {
+    let mut args = std::env::args().skip(1);
+    let mut scene = None;
+    let mut opts = Options {
+        scene: String::new(),
+        width: 1920,
+        height: 1080,
+        manifest: None,
+        out: "frame_{frame}.png".to_string(),
+        ndjson: false,
+        raw: false,
+        premultiplied: false,
+        layout: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("Missing value for {}", name));
+        match arg.as_str() {
+            "-w" | "--width" => opts.width = value(&arg)?.parse().map_err(|_| "Width must be a positive integer")?,
+            "-h" | "--height" => opts.height = value(&arg)?.parse().map_err(|_| "Height must be a positive integer")?,
+            "-m" | "--manifest" => opts.manifest = Some(PathBuf::from(value(&arg)?)),
+            "-o" | "--out" => opts.out = value(&arg)?,
+            "--ndjson" => opts.ndjson = true,
+            "--raw" => opts.raw = true,
+            "--premultiplied" => opts.premultiplied = true,
+            "--layout" => opts.layout = true,
+            "--help" => return Ok(None),
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("Unknown option '{}'\n\n{}", arg, USAGE)),
+            _ if scene.is_none() => scene = Some(arg),
+            _ => return Err(format!("Unexpected argument '{}'\n\n{}", arg, USAGE)),
+        }
+    }
+
+    opts.scene = scene.ok_or_else(|| USAGE.to_string())?;
+    if opts.width == 0 || opts.height == 0 {
+        return Err(format!("Invalid frame size {}x{}: width and height must be positive", opts.width, opts.height));
+    }
+    if opts.scene.ends_with(".ndjson") || opts.scene.ends_with(".jsonl") {
+        opts.ndjson = true;
+    }
+    Ok(Some(opts))
+}
+
+fn load_manifest(engine: &mut EngineCore, path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let manifest: Manifest = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    // Manifest paths are relative to the manifest itself, not the working directory.
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    for (name, file) in &manifest.fonts {
+        let file = base.join(file);
+        let data = fs::read(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        engine.load_font(name, &data).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    for (id, file) in &manifest.assets {
+        let file = base.join(file);
+        let data = fs::read(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        engine.load_asset(id, &data).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    for (name, file) in &manifest.luts {
+        let file = base.join(file);
+        let data = fs::read(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        engine.load_lut(name, &data).map_err(|e| format!("{}: {}", file.display(), e))?;
+    }
+    engine.register_styles(manifest.styles);
+    Ok(())
+}
+
+fn open_scene(path: &str) -> Result<Box<dyn Read>, String> {
+    if path == "-" {
+        Ok(Box::new(io::stdin()))
+    } else {
+        fs::File::open(path).map(|f| Box::new(f) as Box<dyn Read>).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+fn output_path(pattern: &str, frame: usize) -> String {
+    pattern.replace("{frame}", &format!("{:05}", frame))
+}
+
+/// Undoes premultiplied alpha in place, rounding like tiny-skia's PNG export.
+fn demultiply(pixels: &mut [u8]) {
+    for p in pixels.chunks_exact_mut(4) {
+        let a = p[3] as u32;
+        if a == 0 || a == 255 { continue; }
+        for c in &mut p[..3] {
+            *c = ((*c as u32 * 510 + a) / (2 * a)).min(255) as u8;
+        }
+    }
+}
+
+/// Writes a frame; with `--raw` its alpha is demultiplied in place first,
+/// unless `--premultiplied` asks for the engine's own pixels.
+fn write_frame(opts: &Options, frame: usize, pixels: &mut [u8], stdout: &mut impl Write) -> Result<(), String> {
+    if opts.raw {
+        if !opts.premultiplied { demultiply(pixels); }
+        return stdout.write_all(pixels).map_err(|e| format!("stdout: {}", e));
+    }
+
+    let path = output_path(&opts.out, frame);
+    let pixmap = PixmapRef::from_bytes(pixels, opts.width, opts.height).ok_or("Renderer returned a frame of the wrong size")?;
+    if let Some(dir) = Path::new(&path).parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    pixmap.save_png(&path).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run(opts: &Options) -> Result<usize, String> {
+    let mut engine = EngineCore::new();
+    if let Some(manifest) = &opts.manifest {
+        load_manifest(&mut engine, manifest)?;
+    }
+
+    let mut reader = BufReader::new(open_scene(&opts.scene)?);
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut frame = 0;
+    // Every frame is drawn into the same buffer; `--layout` draws nothing.
+    let mut pixels = if opts.layout {
+        Vec::new()
+    } else {
+        let len = (opts.width as usize).checked_mul(opts.height as usize).and_then(|n| n.checked_mul(4))
+            .ok_or_else(|| format!("Frame size {}x{} is too large", opts.width, opts.height))?;
+        vec![0; len]
+    };
+
+    let mut render = |json: &str, frame: usize, stdout: &mut io::StdoutLock| -> Result<(), String> {
+        if opts.layout {
+            let boxes = engine.compute_layout(json, opts.width, opts.height).map_err(|e| format!("Frame {}: {}", frame, e))?;
+            let line = serde_json::to_string(&boxes).map_err(|e| format!("Frame {}: {}", frame, e))?;
+            return writeln!(stdout, "{}", line).map_err(|e| format!("stdout: {}", e));
+        }
+        let warnings = engine.render_into(json, opts.width, opts.height, &mut pixels).map_err(|e| format!("Frame {}: {}", frame, e))?;
+        for warning in &warnings {
+            eprintln!("Frame {}: warning: {}", frame, warning);
+        }
+        write_frame(opts, frame, &mut pixels, stdout)
+    };
+
+    if opts.ndjson {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).map_err(|e| format!("{}: {}", opts.scene, e))? == 0 { break; }
+            if line.trim().is_empty() { continue; }
+            render(&line, frame, &mut stdout)?;
+            frame += 1;
+        }
+    } else {
+        let mut json = String::new();
+        reader.read_to_string(&mut json).map_err(|e| format!("{}: {}", opts.scene, e))?;
+        render(&json, frame, &mut stdout)?;
+        frame += 1;
+    }
+
+    stdout.flush().map_err(|e| format!("stdout: {}", e))?;
+    Ok(frame)
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args() {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&opts) {
+        Ok(frames) => {
+            if !opts.raw && !opts.layout {
+                eprintln!("Rendered {} frame(s)", frames);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
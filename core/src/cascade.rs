@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+use crate::engine::EngineCore;
+use crate::error::RenderWarning;
+use crate::types::{SceneNode, StyleConfig};
+
+/// Resolves `class` names into each node's `style`, in place. A class takes
+/// its properties from the engine's registered stylesheet, overridden by the
+/// scene's own `styles`; a node's classes then apply in order, and its inline
+/// `style` last.
+pub fn cascade(engine: &EngineCore, root: &mut SceneNode) {
+    let scene_styles = root.styles.take().unwrap_or_default();
+    apply_classes(engine, &scene_styles, root);
+}
+
+pub(crate) fn apply_classes(engine: &EngineCore, scene_styles: &HashMap<String, StyleConfig>, node: &mut SceneNode) {
+    if let Some(classes) = &node.class {
+        let mut style = StyleConfig::default();
+        for name in classes.names() {
+            let sources = [engine.stylesheet.get(name), scene_styles.get(name)];
+            if sources.iter().all(Option::is_none) {
+                engine.warn(RenderWarning::MissingClass { name: name.to_string() });
+            }
+            for source in sources.into_iter().flatten() { style.apply(source); }
+        }
+        style.apply(&node.style);
+        node.style = style;
+    }
+    for child in node.children.iter_mut().flatten() { apply_classes(engine, scene_styles, child); }
+    if let Some(mask) = &mut node.mask { apply_classes(engine, scene_styles, mask); }
+}
@@ -2,14 +2,40 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::cell::RefCell;
 use fontdue::{Font, FontSettings};
-use tiny_skia::Pixmap;
-use crate::text::{CachedGlyph, GlyphKey};
+use taffy::prelude::*;
+use tiny_skia::{IntSize, Pixmap, PixmapMut};
+use crate::filters::BlurScratch;
+use crate::lut::Lut;
+use crate::error::{Frame, RenderError, RenderWarning};
+use crate::render::{draw_scene, layout_boxes, LayerPool, LayoutBox, NodeBox, SceneContext, Surface};
+use crate::layout::LayoutContext;
+use crate::text::{CachedGlyph, GlyphKey, TextStyle};
+use crate::cascade::cascade;
+use crate::scene::RetainedScene;
+use crate::types::{SceneNode, StyleConfig};
+
+const KNOWN_TAGS: [&str; 7] = ["view", "text", "image", "circle", "rect", "path", "ellipse"];
 
 pub struct EngineCore {
     pub fonts: HashMap<String, Arc<Font>>,
     pub assets: HashMap<String, Pixmap>, 
+    /// Color grades by name, see `load_lut`.
+    pub luts: HashMap<String, Lut>,
+    /// Classes available to every scene, see `register_styles`.
+    pub stylesheet: HashMap<String, StyleConfig>,
     pub glyph_cache: RefCell<HashMap<GlyphKey, Arc<CachedGlyph>>>,
     pub scratch_buffer: RefCell<Vec<u8>>,
+    /// Layer pixmaps, kept across frames.
+    pub layers: LayerPool,
+    /// Blur buffers, kept across frames.
+    pub blur_scratch: RefCell<BlurScratch>,
+    pub warnings: RefCell<Vec<RenderWarning>>,
+}
+
+impl Default for EngineCore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl EngineCore {
@@ -17,8 +43,13 @@ impl EngineCore {
         EngineCore { 
             fonts: HashMap::new(), 
             assets: HashMap::new(),
+            luts: HashMap::new(),
+            stylesheet: HashMap::new(),
             glyph_cache: RefCell::new(HashMap::new()),
             scratch_buffer: RefCell::new(Vec::new()),
+            layers: LayerPool::default(),
+            blur_scratch: RefCell::new(BlurScratch::default()),
+            warnings: RefCell::new(Vec::new()),
         }
     }
 
@@ -28,6 +59,12 @@ impl EngineCore {
         Ok(())
     }
 
+    /// Adds classes that scenes can use by name without repeating them every frame.
+    /// Registering a name again replaces it.
+    pub fn register_styles(&mut self, styles: HashMap<String, StyleConfig>) {
+        self.stylesheet.extend(styles);
+    }
+
     pub fn load_asset(&mut self, id: &str, data: &[u8]) -> Result<(), String> {
         let img = image::load_from_memory(data).map_err(|e| e.to_string())?;
         let mut rgba = img.to_rgba8();
@@ -42,14 +79,23 @@ impl EngineCore {
                 p[2] = ((p[2] as u16 * a) / 255) as u8;
             }
         }
-        let pixmap = Pixmap::from_vec(rgba.into_raw(), tiny_skia::IntSize::from_wh(width, height).unwrap())
+        let size = IntSize::from_wh(width, height).ok_or_else(|| RenderError::InvalidDimensions { width, height }.to_string())?;
+        let pixmap = Pixmap::from_vec(rgba.into_raw(), size)
             .ok_or_else(|| "Failed to create pixmap".to_string())?;
         self.assets.insert(id.to_string(), pixmap);
         Ok(())
     }
 
+    /// Loads a `.cube` LUT (1D or 3D) that nodes can grade with via `lut`.
+    pub fn load_lut(&mut self, name: &str, data: &[u8]) -> Result<(), String> {
+        let text = std::str::from_utf8(data).map_err(|_| "LUT is not UTF-8 text".to_string())?;
+        self.luts.insert(name.to_string(), Lut::parse(text)?);
+        Ok(())
+    }
+
     // New method for raw texture updates (Video Frames)
     pub fn load_asset_raw(&mut self, id: &str, data: &[u8], width: u32, height: u32) -> Result<(), String> {
+        let size = IntSize::from_wh(width, height).ok_or_else(|| RenderError::InvalidDimensions { width, height }.to_string())?;
         // We assume data is RGBA8. We need to premultiply it.
         let mut pixels = data.to_vec();
         for p in pixels.chunks_exact_mut(4) {
@@ -61,7 +107,7 @@ impl EngineCore {
             }
         }
         
-        let pixmap = Pixmap::from_vec(pixels, tiny_skia::IntSize::from_wh(width, height).unwrap())
+        let pixmap = Pixmap::from_vec(pixels, size)
             .ok_or_else(|| "Failed to create pixmap from raw data".to_string())?;
         self.assets.insert(id.to_string(), pixmap);
         Ok(())
@@ -80,4 +126,125 @@ impl EngineCore {
         self.glyph_cache.borrow_mut().insert(key, glyph.clone());
         glyph
     }
+
+    /// Records a non-fatal problem for the frame currently being rendered.
+    /// Repeats are collapsed so a missing asset used 100 times is reported once.
+    pub fn warn(&self, warning: RenderWarning) {
+        let mut warnings = self.warnings.borrow_mut();
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+
+    pub fn render(&self, json_input: &str, width: u32, height: u32) -> Result<Frame, RenderError> {
+        self.render_scene(serde_json::from_str(json_input)?, width, height)
+    }
+
+    /// Like `render`, for a scene encoded as MessagePack with the same field
+    /// names as the JSON form (see `lib/msgpack.ts`).
+    pub fn render_binary(&self, data: &[u8], width: u32, height: u32) -> Result<Frame, RenderError> {
+        self.render_scene(rmp_serde::from_slice(data)?, width, height)
+    }
+
+    /// Like `render`, but draws into `out`, which must hold exactly
+    /// `width * height * 4` bytes. Returns the frame's warnings.
+    pub fn render_into(&self, json_input: &str, width: u32, height: u32, out: &mut [u8]) -> Result<Vec<RenderWarning>, RenderError> {
+        self.render_scene_into(serde_json::from_str(json_input)?, width, height, out)
+    }
+
+    /// `render_binary` into a caller's buffer, as with `render_into`.
+    pub fn render_binary_into(&self, data: &[u8], width: u32, height: u32, out: &mut [u8]) -> Result<Vec<RenderWarning>, RenderError> {
+        self.render_scene_into(rmp_serde::from_slice(data)?, width, height, out)
+    }
+
+    /// Renders a parsed scene. Its classes are resolved in place, which is why
+    /// it is taken by value.
+    pub fn render_scene(&self, root_node: SceneNode, width: u32, height: u32) -> Result<Frame, RenderError> {
+        let mut pixels = vec![0; frame_len(width, height)?];
+        let warnings = self.render_scene_into(root_node, width, height, &mut pixels)?;
+        Ok(Frame { pixels, warnings })
+    }
+
+    pub fn render_scene_into(&self, root_node: SceneNode, width: u32, height: u32, out: &mut [u8]) -> Result<Vec<RenderWarning>, RenderError> {
+        let expected = frame_len(width, height)?;
+        if out.len() != expected {
+            return Err(RenderError::BufferSize { expected, actual: out.len() });
+        }
+        self.lay_out(root_node, width, height, |taffy, root_node, root| self.draw_frame(taffy, root_node, root, width, height, out))
+    }
+
+    /// Lays out a scene without drawing it, returning every node's box in
+    /// document order.
+    pub fn compute_layout(&self, json_input: &str, width: u32, height: u32) -> Result<Vec<LayoutBox>, RenderError> {
+        if width == 0 || height == 0 {
+            return Err(RenderError::InvalidDimensions { width, height });
+        }
+        self.lay_out(serde_json::from_str(json_input)?, width, height, |taffy, root_node, root| layout_boxes(taffy, self, root_node, root))
+    }
+
+    /// Resolves classes and lays out `root_node` for a `width`x`height` canvas, then hands the result to `f`.
+    fn lay_out<R>(
+        &self, mut root_node: SceneNode, width: u32, height: u32, f: impl FnOnce(&Taffy, &SceneNode, Node) -> Result<R, RenderError>
+    ) -> Result<R, RenderError> {
+        validate_tags(&root_node)?;
+        self.warnings.borrow_mut().clear();
+        cascade(self, &mut root_node);
+
+        let mut taffy = Taffy::new();
+        let layout_ctx = LayoutContext { assets: &self.assets, fonts: &self.fonts, viewport: Size { width: width as f32, height: height as f32 } };
+        let root = crate::layout::build_taffy(&mut taffy, &root_node, &layout_ctx, &TextStyle::default())?;
+        taffy.compute_layout(root, Size {
+            width: AvailableSpace::Definite(width as f32),
+            height: AvailableSpace::Definite(height as f32)
+        })?;
+
+        f(&taffy, &root_node, root)
+    }
+
+    /// Renders a retained scene, recomputing layout only where patches dirtied it.
+    pub fn render_retained(&self, scene: &mut RetainedScene, width: u32, height: u32) -> Result<Frame, RenderError> {
+        let mut pixels = vec![0; frame_len(width, height)?];
+        let warnings = self.render_retained_into(scene, width, height, &mut pixels)?;
+        Ok(Frame { pixels, warnings })
+    }
+
+    /// `render_retained` into a caller's buffer, as with `render_into`.
+    pub fn render_retained_into(&self, scene: &mut RetainedScene, width: u32, height: u32, out: &mut [u8]) -> Result<Vec<RenderWarning>, RenderError> {
+        let expected = frame_len(width, height)?;
+        if out.len() != expected {
+            return Err(RenderError::BufferSize { expected, actual: out.len() });
+        }
+        *self.warnings.borrow_mut() = scene.warnings().to_vec();
+        let (root_node, taffy, root) = scene.layout(self, Size { width: width as f32, height: height as f32 })?;
+        self.draw_frame(taffy, root_node, root, width, height, out)
+    }
+
+    /// Draws a laid-out scene over `out`, which is cleared first.
+    fn draw_frame(&self, taffy: &Taffy, root_node: &SceneNode, root: Node, width: u32, height: u32, out: &mut [u8]) -> Result<Vec<RenderWarning>, RenderError> {
+        out.fill(0);
+        let pixmap = PixmapMut::from_bytes(out, width, height).ok_or(RenderError::PixmapAllocation { width, height })?;
+        let ctx = SceneContext { taffy, engine: self, canvas_width: width, canvas_height: height };
+        draw_scene(&ctx, root_node, root, &mut Surface::new(pixmap, (0, 0)), &NodeBox::canvas())?;
+
+        Ok(self.warnings.take())
+    }
+}
+
+/// Bytes in a `width`x`height` RGBA frame.
+fn frame_len(width: u32, height: u32) -> Result<usize, RenderError> {
+    if width == 0 || height == 0 {
+        return Err(RenderError::InvalidDimensions { width, height });
+    }
+    IntSize::from_wh(width, height)
+        .and_then(|_| (width as usize).checked_mul(height as usize)?.checked_mul(4))
+        .ok_or(RenderError::PixmapAllocation { width, height })
+}
+
+pub(crate) fn validate_tags(node: &SceneNode) -> Result<(), RenderError> {
+    if !KNOWN_TAGS.contains(&node.tag.as_str()) {
+        return Err(RenderError::UnknownTag(node.tag.clone()));
+    }
+    if let Some(mask) = &node.mask { validate_tags(mask)?; }
+    for child in node.children.iter().flatten() { validate_tags(child)?; }
+    Ok(())
 }
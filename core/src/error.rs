@@ -0,0 +1,85 @@
+use std::fmt;
+use serde::Serialize;
+
+/// Fatal problems that stop a frame from being rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    Json { message: String, line: usize, column: usize },
+    /// A MessagePack scene that does not decode to the scene schema.
+    Binary(String),
+    UnknownTag(String),
+    InvalidDimensions { width: u32, height: u32 },
+    Layout(String),
+    PixmapAllocation { width: u32, height: u32 },
+    /// A buffer passed to `render_into` that does not fit the frame.
+    BufferSize { expected: usize, actual: usize },
+    UnknownNode(String),
+    InvalidPatch(String),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::Json { message, line, column } => write!(f, "Invalid scene JSON at line {}, column {}: {}", line, column, message),
+            RenderError::Binary(message) => write!(f, "Invalid binary scene: {}", message),
+            RenderError::UnknownTag(tag) => write!(f, "Unknown node tag '{}'", tag),
+            RenderError::InvalidDimensions { width, height } => write!(f, "Invalid render dimensions {}x{}", width, height),
+            RenderError::Layout(message) => write!(f, "Layout failed: {}", message),
+            RenderError::PixmapAllocation { width, height } => write!(f, "Failed to allocate {}x{} pixmap", width, height),
+            RenderError::BufferSize { expected, actual } => write!(f, "Frame buffer holds {} bytes, expected {}", actual, expected),
+            RenderError::UnknownNode(id) => write!(f, "No node with id '{}'", id),
+            RenderError::InvalidPatch(message) => write!(f, "Invalid patch: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+impl From<serde_json::Error> for RenderError {
+    fn from(e: serde_json::Error) -> Self {
+        // serde_json appends the position to its message; we report it separately.
+        let suffix = format!(" at line {} column {}", e.line(), e.column());
+        let message = e.to_string();
+        let message = message.strip_suffix(&suffix).unwrap_or(&message).to_string();
+        RenderError::Json { message, line: e.line(), column: e.column() }
+    }
+}
+
+impl From<rmp_serde::decode::Error> for RenderError {
+    fn from(e: rmp_serde::decode::Error) -> Self {
+        RenderError::Binary(e.to_string())
+    }
+}
+
+impl From<taffy::error::TaffyError> for RenderError {
+    fn from(e: taffy::error::TaffyError) -> Self {
+        RenderError::Layout(e.to_string())
+    }
+}
+
+/// Non-fatal problems: the frame is still produced, but something was skipped.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RenderWarning {
+    MissingAsset { src: String },
+    MissingFont { family: String },
+    MissingClass { name: String },
+    MissingLut { name: String },
+}
+
+impl fmt::Display for RenderWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderWarning::MissingAsset { src } => write!(f, "Asset '{}' is not loaded", src),
+            RenderWarning::MissingFont { family } => write!(f, "No font available for '{}'", family),
+            RenderWarning::MissingClass { name } => write!(f, "Class '{}' is not defined", name),
+            RenderWarning::MissingLut { name } => write!(f, "LUT '{}' is not loaded", name),
+        }
+    }
+}
+
+/// A rendered frame: premultiplied RGBA pixels plus anything worth reporting.
+pub struct Frame {
+    pub pixels: Vec<u8>,
+    pub warnings: Vec<RenderWarning>,
+}
@@ -1,81 +1,518 @@
-use tiny_skia::Pixmap;
-use image::{ImageBuffer, Rgba};
-use crate::types::StyleConfig;
-
-pub fn apply_image_filters(pixmap: &mut Pixmap, style: &StyleConfig) {
-    let gs = style.grayscale.unwrap_or(0.0).clamp(0.0, 1.0);
-    let br = style.brightness.unwrap_or(1.0).max(0.0);
-    let ct = style.contrast.unwrap_or(1.0).max(0.0);
-    let sat = style.saturation.unwrap_or(1.0).max(0.0);
-    let inv = style.invert.unwrap_or(0.0).clamp(0.0, 1.0);
-    let sep = style.sepia.unwrap_or(0.0).clamp(0.0, 1.0);
-    let blur_radius = style.blur.unwrap_or(0.0).max(0.0);
-
-    let has_color_matrix = gs != 0.0 || br != 1.0 || ct != 1.0 || sat != 1.0 || inv != 0.0 || sep != 0.0;
-
-    if has_color_matrix {
-        let data = pixmap.data_mut();
-        for i in (0..data.len()).step_by(4) {
-            let alpha = data[i+3];
-            if alpha == 0 { continue; }
-            let a_f = alpha as f32 / 255.0;
-            
-            // Un-premultiply
-            let mut r = (data[i] as f32 / 255.0) / a_f;
-            let mut g = (data[i+1] as f32 / 255.0) / a_f;
-            let mut b = (data[i+2] as f32 / 255.0) / a_f;
-
-            // 1. Grayscale & Saturation
-            let lum = 0.2126 * r + 0.7152 * g + 0.0722 * b;
-            let sat_r = lum * (1.0 - sat) + r * sat;
-            let sat_g = lum * (1.0 - sat) + g * sat;
-            let sat_b = lum * (1.0 - sat) + b * sat;
-
-            r = sat_r * (1.0 - gs) + lum * gs;
-            g = sat_g * (1.0 - gs) + lum * gs;
-            b = sat_b * (1.0 - gs) + lum * gs;
-
-            // 2. Contrast
-            if ct != 1.0 {
-                r = (r - 0.5) * ct + 0.5;
-                g = (g - 0.5) * ct + 0.5;
-                b = (b - 0.5) * ct + 0.5;
+use tiny_skia::{Color, IntSize, Pixmap, Point, Transform};
+use crate::types::{FilterOp, StyleConfig};
+use crate::utils::parse_color;
+
+/// The filters `style` applies, in order: the scalar shorthands (`saturation`,
+/// `grayscale`, `contrast`, `brightness`, `invert`, `sepia`, then `blur`),
+/// followed by the `filter` list. Shorthands at their neutral value are left out.
+pub fn filter_ops(style: &StyleConfig) -> Vec<FilterOp> {
+    let shorthands = [
+        style.saturation.filter(|s| *s != 1.0).map(|amount| FilterOp::Saturate { amount }),
+        style.grayscale.filter(|g| *g != 0.0).map(|amount| FilterOp::Grayscale { amount }),
+        style.contrast.filter(|c| *c != 1.0).map(|amount| FilterOp::Contrast { amount }),
+        style.brightness.filter(|b| *b != 1.0).map(|amount| FilterOp::Brightness { amount }),
+        style.invert.filter(|i| *i != 0.0).map(|amount| FilterOp::Invert { amount }),
+        style.sepia.filter(|s| *s != 0.0).map(|amount| FilterOp::Sepia { amount }),
+        style.blur.filter(|r| *r > 0.0).map(|radius| FilterOp::Blur { radius }),
+    ];
+    shorthands.into_iter().flatten().chain(style.filter.iter().flatten().cloned()).collect()
+}
+
+pub fn has_filters(style: &StyleConfig) -> bool {
+    !filter_ops(style).is_empty()
+}
+
+/// Where a filtered pixmap lies: the canvas position of its top-left pixel,
+/// and the box of the node being filtered, as a `width`x`height` rectangle
+/// that `transform` maps onto the canvas.
+#[derive(Clone, Copy)]
+pub struct FilterRegion {
+    pub origin: (i32, i32),
+    pub transform: Transform,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Applies `ops` to `pixmap` in order. Consecutive color filters run in one
+/// pass, clamping only at its end. `edge` is what blurs see past the edges.
+pub fn apply_filters(pixmap: &mut Pixmap, ops: &[FilterOp], region: &FilterRegion, edge: EdgeMode, scratch: &mut BlurScratch) {
+    let mut rest = ops;
+    while let Some(op) = rest.first() {
+        if is_color_filter(op) {
+            let run = rest.iter().position(|op| !is_color_filter(op)).unwrap_or(rest.len());
+            apply_color_filters(pixmap, &rest[..run]);
+            rest = &rest[run..];
+            continue;
+        }
+        match op {
+            FilterOp::Blur { radius } => gaussian_blur(pixmap, *radius, edge, scratch),
+            FilterOp::UnsharpMask { radius, amount, threshold } => unsharp_mask(pixmap, *radius, *amount, threshold.unwrap_or(0.0), edge, scratch),
+            FilterOp::Vignette { radius, softness, color } => {
+                let color = color.as_deref().map(parse_color).unwrap_or(Color::BLACK);
+                vignette(pixmap, region, radius.unwrap_or(0.5), softness.unwrap_or(0.5), color);
             }
+            FilterOp::FilmGrain { amount, seed, frame } => film_grain(pixmap, region.origin, *amount, seed.unwrap_or(0), frame.unwrap_or(0)),
+            FilterOp::ChromaticAberration { offset_x, offset_y } => chromatic_aberration(pixmap, *offset_x, *offset_y, &mut scratch.copy),
+            _ => {}
+        }
+        rest = &rest[1..];
+    }
+}
+
+/// Filters that map each color on its own, and so can share one pass.
+fn is_color_filter(op: &FilterOp) -> bool {
+    matches!(op,
+        FilterOp::Grayscale { .. } | FilterOp::Brightness { .. } | FilterOp::Contrast { .. } | FilterOp::Saturate { .. }
+        | FilterOp::Invert { .. } | FilterOp::Sepia { .. } | FilterOp::HueRotate { .. })
+}
+
+fn apply_color_filters(pixmap: &mut Pixmap, ops: &[FilterOp]) {
+    let data = pixmap.data_mut();
+    for i in (0..data.len()).step_by(4) {
+        let alpha = data[i+3];
+        if alpha == 0 { continue; }
+        let a_f = alpha as f32 / 255.0;
+
+        // Un-premultiply
+        let mut r = (data[i] as f32 / 255.0) / a_f;
+        let mut g = (data[i+1] as f32 / 255.0) / a_f;
+        let mut b = (data[i+2] as f32 / 255.0) / a_f;
+
+        // Each filter's output is clamped before the next one sees it, as in CSS.
+        for op in ops {
+            let (nr, ng, nb) = color_filter(op, r, g, b);
+            (r, g, b) = (nr.clamp(0.0, 1.0), ng.clamp(0.0, 1.0), nb.clamp(0.0, 1.0));
+        }
+
+        // Re-premultiply
+        data[i] = (r * a_f * 255.0) as u8;
+        data[i+1] = (g * a_f * 255.0) as u8;
+        data[i+2] = (b * a_f * 255.0) as u8;
+    }
+}
+
+/// One color filter on an un-premultiplied color.
+fn color_filter(op: &FilterOp, r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let mix = |amount: f32, (tr, tg, tb): (f32, f32, f32)| (r * (1.0 - amount) + tr * amount, g * (1.0 - amount) + tg * amount, b * (1.0 - amount) + tb * amount);
+    let lum = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    match *op {
+        FilterOp::Saturate { amount } => {
+            let sat = amount.max(0.0);
+            (lum * (1.0 - sat) + r * sat, lum * (1.0 - sat) + g * sat, lum * (1.0 - sat) + b * sat)
+        }
+        FilterOp::Grayscale { amount } => mix(amount.clamp(0.0, 1.0), (lum, lum, lum)),
+        FilterOp::Contrast { amount } => {
+            let ct = amount.max(0.0);
+            ((r - 0.5) * ct + 0.5, (g - 0.5) * ct + 0.5, (b - 0.5) * ct + 0.5)
+        }
+        FilterOp::Brightness { amount } => {
+            let br = amount.max(0.0);
+            (r * br, g * br, b * br)
+        }
+        FilterOp::Invert { amount } => mix(amount.clamp(0.0, 1.0), (1.0 - r, 1.0 - g, 1.0 - b)),
+        FilterOp::Sepia { amount } => mix(amount.clamp(0.0, 1.0), (
+            (r * 0.393) + (g * 0.769) + (b * 0.189),
+            (r * 0.349) + (g * 0.686) + (b * 0.168),
+            (r * 0.272) + (g * 0.534) + (b * 0.131),
+        )),
+        FilterOp::HueRotate { angle } => {
+            // The CSS `hue-rotate()` matrix.
+            let (sin, cos) = angle.to_radians().sin_cos();
+            (
+                r * (0.213 + cos * 0.787 - sin * 0.213) + g * (0.715 - cos * 0.715 - sin * 0.715) + b * (0.072 - cos * 0.072 + sin * 0.928),
+                r * (0.213 - cos * 0.213 + sin * 0.143) + g * (0.715 + cos * 0.285 + sin * 0.140) + b * (0.072 - cos * 0.072 - sin * 0.283),
+                r * (0.213 - cos * 0.213 - sin * 0.787) + g * (0.715 - cos * 0.715 + sin * 0.715) + b * (0.072 + cos * 0.928 + sin * 0.072),
+            )
+        }
+        _ => (r, g, b),
+    }
+}
+
+/// Sharpens by pushing each pixel's color away from a blurred copy of the
+/// pixmap. Colors are compared un-premultiplied, so transparent surroundings
+/// count for nothing rather than as black, and alpha is left alone.
+fn unsharp_mask(pixmap: &mut Pixmap, sigma: f32, amount: f32, threshold: f32, edge: EdgeMode, scratch: &mut BlurScratch) {
+    if sigma.is_nan() || sigma <= 0.0 || amount == 0.0 { return; }
+    let mut data = std::mem::take(&mut scratch.copy);
+    data.clear();
+    data.extend_from_slice(pixmap.data());
+    let Some(mut blurred) = IntSize::from_wh(pixmap.width(), pixmap.height()).and_then(|size| Pixmap::from_vec(data, size)) else { return };
+    gaussian_blur(&mut blurred, sigma, edge, scratch);
+
+    for (p, b) in pixmap.data_mut().chunks_exact_mut(4).zip(blurred.data().chunks_exact(4)) {
+        if p[3] == 0 || b[3] == 0 { continue; }
+        let (a, ba) = (p[3] as f32, b[3] as f32);
+        for c in 0..3 {
+            let color = p[c] as f32 / a;
+            let diff = color - b[c] as f32 / ba;
+            if diff.abs() * 255.0 <= threshold { continue; }
+            p[c] = ((color + diff * amount).clamp(0.0, 1.0) * a).round() as u8;
+        }
+    }
+    scratch.copy = blurred.take();
+}
+
+/// Mixes each pixel towards `color` by how far out it lies in the region's
+/// node box, keeping its alpha.
+fn vignette(pixmap: &mut Pixmap, region: &FilterRegion, radius: f32, softness: f32, color: Color) {
+    let Some(to_local) = region.transform.invert() else { return };
+    if region.width <= 0.0 || region.height <= 0.0 { return; }
+    let (ox, oy) = (region.origin.0 as f32 + 0.5, region.origin.1 as f32 + 0.5);
+    let width = pixmap.width() as usize;
+    let target = [color.red(), color.green(), color.blue()];
+
+    for (i, p) in pixmap.data_mut().chunks_exact_mut(4).enumerate() {
+        if p[3] == 0 { continue; }
+        let mut point = [Point::from_xy((i % width) as f32 + ox, (i / width) as f32 + oy)];
+        to_local.map_points(&mut point);
+        // 0 at the centre, 1 at the corners.
+        let u = point[0].x / region.width * 2.0 - 1.0;
+        let v = point[0].y / region.height * 2.0 - 1.0;
+        let distance = ((u * u + v * v) / 2.0).sqrt();
+        let t = if softness > 0.0 { ((distance - radius) / softness).clamp(0.0, 1.0) } else if distance >= radius { 1.0 } else { 0.0 };
+        // Smoothstep, so the fade has no visible edge where it starts.
+        let t = t * t * (3.0 - 2.0 * t) * color.alpha();
+        if t == 0.0 { continue; }
+        let a = p[3] as f32;
+        for c in 0..3 { p[c] = (p[c] as f32 * (1.0 - t) + target[c] * a * t).round() as u8; }
+    }
+}
+
+/// Adds the same noise to all three channels of each pixel. The noise comes
+/// from a hash of the pixel's canvas position, `seed` and `frame`.
+fn film_grain(pixmap: &mut Pixmap, origin: (i32, i32), amount: f32, seed: u32, frame: u32) {
+    if amount <= 0.0 { return; }
+    let width = pixmap.width() as usize;
+    let key = hash(seed ^ hash(frame.wrapping_add(0x9e37_79b9)));
+    for (i, p) in pixmap.data_mut().chunks_exact_mut(4).enumerate() {
+        if p[3] == 0 { continue; }
+        let x = (i % width) as i32 + origin.0;
+        let y = (i / width) as i32 + origin.1;
+        let h = hash(key ^ hash(x as u32 ^ hash(y as u32)));
+        // The sum of two uniform values, centred on 0: a triangular
+        // distribution in -1..1, which reads more like grain than uniform noise.
+        let noise = ((h & 0xffff) as f32 + (h >> 16) as f32) / 65535.0 - 1.0;
+        let a = p[3] as f32;
+        let offset = noise * amount * a;
+        for v in &mut p[..3] { *v = (*v as f32 + offset).round().clamp(0.0, a) as u8; }
+    }
+}
+
+/// A 32-bit integer hash (lowbias32) with good avalanche.
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^ (x >> 16)
+}
+
+/// Samples red at `-offset` and blue at `+offset` from each pixel, so the red
+/// channel moves by `offset` and blue by the opposite. Alpha is the largest of
+/// the three samples', which keeps every channel within it.
+fn chromatic_aberration(pixmap: &mut Pixmap, dx: f32, dy: f32, copy: &mut Vec<u8>) {
+    if dx == 0.0 && dy == 0.0 { return; }
+    let (w, h) = (pixmap.width() as usize, pixmap.height() as usize);
+    copy.clear();
+    copy.extend_from_slice(pixmap.data());
+
+    for (i, dst) in pixmap.data_mut().chunks_exact_mut(4).enumerate() {
+        let (fx, fy) = ((i % w) as f32, (i / w) as f32);
+        let red = bilinear(copy, w, h, fx - dx, fy - dy);
+        let green = pixel(copy, i);
+        let blue = bilinear(copy, w, h, fx + dx, fy + dy);
+        let a = red[3].max(green[3]).max(blue[3]);
+        dst.copy_from_slice(&[red[0], green[1], blue[2], a].map(|v| v.round().min(255.0) as u8));
+    }
+}
+
+/// The `i`th pixel of RGBA bytes.
+fn pixel(src: &[u8], i: usize) -> [f32; 4] {
+    std::array::from_fn(|c| src[i * 4 + c] as f32)
+}
+
+/// The pixel at a fractional position, transparent outside the pixmap.
+fn bilinear(src: &[u8], w: usize, h: usize, x: f32, y: f32) -> [f32; 4] {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (x - x0, y - y0);
+    let at = |x: f32, y: f32| {
+        if x < 0.0 || y < 0.0 || x >= w as f32 || y >= h as f32 { return [0.0; 4]; }
+        pixel(src, y as usize * w + x as usize)
+    };
+    let (a, b, c, d) = (at(x0, y0), at(x0 + 1.0, y0), at(x0, y0 + 1.0), at(x0 + 1.0, y0 + 1.0));
+    std::array::from_fn(|i| {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        top + (bottom - top) * ty
+    })
+}
+
+/// What a blur reads past the edges of the pixmap.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeMode {
+    /// Nothing: pixels outside are transparent.
+    Transparent,
+    /// The nearest edge pixel, repeated.
+    Clamp,
+}
+
+/// Buffers kept between filters so that blurring and the like do not allocate once warmed up.
+#[derive(Default)]
+pub struct BlurScratch {
+    /// The blurred area after the horizontal passes, stored column-major.
+    transposed: Vec<[u8; 4]>,
+    line: Vec<[u8; 4]>,
+    back: Vec<[u8; 4]>,
+    kernel: Vec<u32>,
+    /// A copy of the pixmap, for filters that read the original while writing.
+    copy: Vec<u8>,
+}
+
+/// Below this, three box blurs are too coarse an approximation and the
+/// Gaussian kernel itself is small enough to use directly.
+const BOX_MIN_SIGMA: f32 = 2.0;
+
+/// Fixed-point weights sum to this.
+const ONE: u32 = 1 << 16;
+
+/// Larger blurs are clamped to this, which keeps box sums within a `u32`.
+pub const MAX_SIGMA: f32 = 1e6;
 
-            // 3. Brightness
-            r *= br; g *= br; b *= br;
+/// Gaussian blur with standard deviation `sigma`, in place, on premultiplied
+/// pixels. Each axis is a separate pass: three box blurs in a row for larger
+/// `sigma`, the exact kernel for small ones.
+///
+/// With `EdgeMode::Transparent` only the area the content can reach is
+/// processed.
+pub fn gaussian_blur(pixmap: &mut Pixmap, sigma: f32, edge: EdgeMode, scratch: &mut BlurScratch) {
+    if sigma.is_nan() || sigma <= 0.0 { return; }
+    let sigma = sigma.min(MAX_SIGMA);
+    let BlurScratch { transposed, line, back, kernel, .. } = scratch;
+    let passes = if sigma < BOX_MIN_SIGMA {
+        gaussian_kernel(sigma, kernel);
+        Passes::Kernel(kernel)
+    } else {
+        Passes::Boxes(box_radii(sigma))
+    };
 
-            // 4. Invert
-            if inv > 0.0 {
-                r = r * (1.0 - inv) + (1.0 - r) * inv;
-                g = g * (1.0 - inv) + (1.0 - g) * inv;
-                b = b * (1.0 - inv) + (1.0 - b) * inv;
+    let (w, h) = (pixmap.width() as usize, pixmap.height() as usize);
+    // `pad` is transparent room past each edge of the area, (left, top, right, bottom).
+    let (x0, y0, aw, ah, pad) = match edge {
+        EdgeMode::Clamp => (0, 0, w, h, [0; 4]),
+        EdgeMode::Transparent => {
+            let Some((cx0, cy0, cx1, cy1)) = content_bounds(pixmap) else { return };
+            // Spread this thin, even an opaque row averages out well under
+            // half a step in the first pass, and rounds to nothing.
+            if let Passes::Boxes([r, ..]) = passes {
+                if 2 * r + 1 > 4 * 255 * (cx1 - cx0) {
+                    pixmap.fill(Color::TRANSPARENT);
+                    return;
+                }
             }
+            let reach = passes.reach();
+            let (x0, y0) = (cx0.saturating_sub(reach), cy0.saturating_sub(reach));
+            let (x1, y1) = ((cx1 + reach).min(w), (cy1 + reach).min(h));
+            // Each box pass spreads what the ones before it carried, so where
+            // the pixmap cuts the area short they need room to carry it past
+            // the edge and back.
+            let pad = match passes {
+                Passes::Boxes(_) => [reach.saturating_sub(cx0), reach.saturating_sub(cy0), (cx1 + reach).saturating_sub(w), (cy1 + reach).saturating_sub(h)],
+                Passes::Kernel(_) => [0; 4],
+            };
+            (x0, y0, x1 - x0, y1 - y0, pad)
+        }
+    };
+    let pixels = pixmap.data_mut();
+
+    transposed.clear();
+    transposed.resize(aw * ah, [0; 4]);
+    for y in 0..ah {
+        let row = (y0 + y) * w + x0;
+        line.clear();
+        line.resize(pad[0], [0; 4]);
+        line.extend(pixels[row * 4..(row + aw) * 4].chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]));
+        line.resize(pad[0] + aw + pad[2], [0; 4]);
+        passes.run(line, back, edge);
+        for (x, p) in line[pad[0]..pad[0] + aw].iter().enumerate() { transposed[x * ah + y] = *p; }
+    }
+    for x in 0..aw {
+        line.clear();
+        line.resize(pad[1], [0; 4]);
+        line.extend_from_slice(&transposed[x * ah..(x + 1) * ah]);
+        line.resize(pad[1] + ah + pad[3], [0; 4]);
+        passes.run(line, back, edge);
+        for (y, p) in line[pad[1]..pad[1] + ah].iter().enumerate() {
+            let i = ((y0 + y) * w + x0 + x) * 4;
+            pixels[i..i + 4].copy_from_slice(p);
+        }
+    }
+}
+
+enum Passes<'a> {
+    /// Radii of three box blurs.
+    Boxes([usize; 3]),
+    /// Weights from the centre outwards, summing to `ONE` over both sides.
+    Kernel(&'a [u32]),
+}
+
+impl Passes<'_> {
+    /// How far one axis of the blur carries a pixel.
+    fn reach(&self) -> usize {
+        match self {
+            Passes::Boxes(radii) => radii.iter().sum(),
+            Passes::Kernel(kernel) => kernel.len() - 1,
+        }
+    }
 
-            // 5. Sepia
-            if sep > 0.0 {
-                let sr = (r * 0.393) + (g * 0.769) + (b * 0.189);
-                let sg = (r * 0.349) + (g * 0.686) + (b * 0.168);
-                let sb = (r * 0.272) + (g * 0.534) + (b * 0.131);
-                r = r * (1.0 - sep) + sr * sep;
-                g = g * (1.0 - sep) + sg * sep;
-                b = b * (1.0 - sep) + sb * sep;
+    /// Blurs `line` in place, using `back` as scratch.
+    fn run(&self, line: &mut Vec<[u8; 4]>, back: &mut Vec<[u8; 4]>, edge: EdgeMode) {
+        back.clear();
+        back.resize(line.len(), [0; 4]);
+        match self {
+            Passes::Boxes(radii) => {
+                for &r in radii {
+                    box_line(line, back, r, edge);
+                    std::mem::swap(line, back);
+                }
+            }
+            Passes::Kernel(kernel) => {
+                kernel_line(line, back, kernel, edge);
+                std::mem::swap(line, back);
             }
+        }
+    }
+}
+
+/// The smallest rectangle (`x0, y0, x1, y1`, exclusive) holding every pixel
+/// that is not fully transparent.
+fn content_bounds(pixmap: &Pixmap) -> Option<(usize, usize, usize, usize)> {
+    let w = pixmap.width() as usize;
+    let rows = || pixmap.data().chunks_exact(w * 4);
+    let visible = |p: &[u8]| p[3] != 0;
+    let filled: Vec<usize> = rows().enumerate().filter(|(_, row)| row.chunks_exact(4).any(visible)).map(|(y, _)| y).collect();
+    let (&y0, &y1) = (filled.first()?, filled.last()?);
+    let (mut x0, mut x1) = (w, 0);
+    for row in rows().skip(y0).take(y1 + 1 - y0) {
+        if let Some(first) = row.chunks_exact(4).position(visible) {
+            x0 = x0.min(first);
+            x1 = x1.max(row.chunks_exact(4).rposition(visible).unwrap_or(first) + 1);
+        }
+    }
+    Some((x0, y0, x1, y1 + 1))
+}
+
+/// Radii of three box blurs whose combined variance is closest to `sigma`²
+/// ("Fast Almost-Gaussian Filtering", Kovesi).
+fn box_radii(sigma: f32) -> [usize; 3] {
+    const N: f32 = 3.0;
+    let ideal = (12.0 * sigma * sigma / N + 1.0).sqrt();
+    let mut lower = ideal.floor() as i32;
+    if lower % 2 == 0 { lower -= 1; }
+    let upper = lower + 2;
+    let lf = lower as f32;
+    let lower_count = ((12.0 * sigma * sigma - N * lf * lf - 4.0 * N * lf - 3.0 * N) / (-4.0 * lf - 4.0)).round() as i32;
+    [0, 1, 2].map(|i| ((if i < lower_count { lower } else { upper } - 1) / 2) as usize)
+}
 
-            // Re-premultiply and clamp
-            data[i] = ((r * a_f * 255.0).clamp(0.0, 255.0)) as u8;
-            data[i+1] = ((g * a_f * 255.0).clamp(0.0, 255.0)) as u8;
-            data[i+2] = ((b * a_f * 255.0).clamp(0.0, 255.0)) as u8;
+/// Fixed-point Gaussian weights for `sigma`, out to 3σ, into `kernel`.
+fn gaussian_kernel(sigma: f32, kernel: &mut Vec<u32>) {
+    let radius = (sigma * 3.0).ceil().max(1.0) as usize;
+    let weight = |i: usize| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp();
+    let total = 1.0 + 2.0 * (1..=radius).map(weight).sum::<f32>();
+    kernel.clear();
+    kernel.extend((0..=radius).map(|i| (weight(i) / total * ONE as f32).round() as u32));
+    // Put any rounding error in the centre so the weights sum to exactly `ONE`.
+    let sum = kernel[0] + 2 * kernel[1..].iter().sum::<u32>();
+    kernel[0] = (kernel[0] + ONE).saturating_sub(sum);
+}
+
+/// `line[j]`, or what lies past its ends.
+#[inline]
+fn sample(line: &[[u8; 4]], j: isize, edge: EdgeMode) -> [u32; 4] {
+    let p = if j < 0 {
+        if edge == EdgeMode::Transparent { return [0; 4]; }
+        line[0]
+    } else if j as usize >= line.len() {
+        if edge == EdgeMode::Transparent { return [0; 4]; }
+        line[line.len() - 1]
+    } else {
+        line[j as usize]
+    };
+    p.map(u32::from)
+}
+
+/// Box blur of radius `r` from `src` into `dst`.
+///
+/// The window slides in three runs: where its tail is still past the start,
+/// where it lies wholly inside the line, and where its head is past the end.
+/// Only the ends look at `edge`, so the middle run indexes nothing out of
+/// bounds and compiles to straight-line lane arithmetic.
+fn box_line(src: &[[u8; 4]], dst: &mut [[u8; 4]], r: usize, edge: EdgeMode) {
+    if r == 0 {
+        dst.copy_from_slice(src);
+        return;
+    }
+    let div = (2 * r + 1) as u32;
+    // Dividing by multiplying: `sum * inv >> 24` stays within u32 for any
+    // sum of `div` bytes.
+    let inv = ((1 << 24) + div / 2) / div;
+    let n = src.len();
+    let mut sum = [0u32; 4];
+    for p in &src[0..(r + 1).min(n)] {
+        for c in 0..4 { sum[c] += p[c] as u32; }
+    }
+    // The window's samples past either end, counted rather than visited.
+    let (before, after) = (r as u32, (r + 1).saturating_sub(n) as u32);
+    let (first, last) = (sample(src, -1, edge), sample(src, n as isize, edge));
+    for c in 0..4 { sum[c] += first[c] * before + last[c] * after; }
+
+    let step = |sum: &mut [u32; 4], out: &mut [u8; 4], add: [u32; 4], sub: [u32; 4]| {
+        *out = sum.map(|s| ((s * inv + (1 << 23)) >> 24).min(255) as u8);
+        for c in 0..4 { sum[c] = sum[c] + add[c] - sub[c]; }
+    };
+    let widen = |p: &[u8; 4]| p.map(u32::from);
+    let head = r.min(n);
+    let tail = n.saturating_sub(r + 1).max(head);
+    for (i, out) in dst[..head].iter_mut().enumerate() {
+        step(&mut sum, out, src.get(i + r + 1).map_or(last, widen), first);
+    }
+    if head < tail {
+        for ((out, add), sub) in dst[head..tail].iter_mut().zip(&src[head + r + 1..]).zip(&src[head - r..]) {
+            step(&mut sum, out, widen(add), widen(sub));
         }
     }
+    for (out, sub) in dst[tail..].iter_mut().zip(&src[tail.saturating_sub(r)..]) {
+        step(&mut sum, out, last, widen(sub));
+    }
+}
 
-    if blur_radius > 0.0 {
-        let w = pixmap.width();
-        let h = pixmap.height();
-        if let Some(img_buffer) = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(w, h, pixmap.data().to_vec()) {
-            let blurred = image::imageops::blur(&img_buffer, blur_radius);
-            let data = pixmap.data_mut();
-            data.copy_from_slice(blurred.as_raw());
+/// Convolution of `src` with a symmetric `kernel` into `dst`.
+///
+/// As with `box_line`, only pixels within the kernel's radius of an end look
+/// at `edge`; the rest read their neighbours straight from `src`.
+fn kernel_line(src: &[[u8; 4]], dst: &mut [[u8; 4]], kernel: &[u32], edge: EdgeMode) {
+    let radius = kernel.len() - 1;
+    let n = src.len();
+    let finish = |sum: [u32; 4]| sum.map(|s| ((s + ONE / 2) >> 16).min(255) as u8);
+    let at_edge = |i: usize| {
+        let i = i as isize;
+        let mut sum = sample(src, i, edge).map(|v| v * kernel[0]);
+        for (k, &weight) in kernel.iter().enumerate().skip(1) {
+            let (left, right) = (sample(src, i - k as isize, edge), sample(src, i + k as isize, edge));
+            for c in 0..4 { sum[c] += (left[c] + right[c]) * weight; }
+        }
+        finish(sum)
+    };
+    let head = radius.min(n);
+    let tail = n.saturating_sub(radius).max(head);
+    for i in (0..head).chain(tail..n) { dst[i] = at_edge(i); }
+    for (i, out) in dst.iter_mut().enumerate().take(tail).skip(head) {
+        let window = &src[i - radius..=i + radius];
+        let mut sum = window[radius].map(|v| u32::from(v) * kernel[0]);
+        for (k, &weight) in kernel.iter().enumerate().skip(1) {
+            let (left, right) = (window[radius - k], window[radius + k]);
+            for c in 0..4 { sum[c] += (u32::from(left[c]) + u32::from(right[c])) * weight; }
         }
+        *out = finish(sum);
     }
-}
\ No newline at end of file
+}
@@ -5,67 +5,207 @@ use std::sync::Arc;
 use fontdue::Font;
 use tiny_skia::Pixmap;
 
-use crate::types::SceneNode;
-use crate::text::compute_text_lines;
+use crate::error::RenderError;
+use crate::types::{GridLine, GridLines, GridTemplate, GridTrack, Length, SceneNode};
+use crate::text::{compute_text_lines, TextStyle};
 
-pub fn build_taffy(
-    taffy: &mut Taffy, 
-    node: &SceneNode, 
-    assets: &HashMap<String, Pixmap>, 
-    fonts: &HashMap<String, Arc<Font>>
-) -> Node {
-    let mut w = node.style.width.map(Dimension::Points).unwrap_or(Dimension::Auto);
-    let mut h = node.style.height.map(Dimension::Points).unwrap_or(Dimension::Auto);
+/// What `build_taffy` needs besides the node itself.
+pub struct LayoutContext<'a> {
+    pub assets: &'a HashMap<String, Pixmap>,
+    pub fonts: &'a HashMap<String, Arc<Font>>,
+    /// The render size, which `vw` and `vh` are relative to.
+    pub viewport: Size<f32>,
+}
+
+/// Resolves viewport and em units to points; percentages stay relative to the parent.
+fn dimension(value: Option<Length>, ctx: &LayoutContext, font_size: f32) -> Dimension {
+    match value {
+        Some(Length::Px(px)) => Dimension::Points(px),
+        Some(Length::Percent(p)) => Dimension::Percent(p / 100.0),
+        Some(Length::Vw(v)) => Dimension::Points(v / 100.0 * ctx.viewport.width),
+        Some(Length::Vh(v)) => Dimension::Points(v / 100.0 * ctx.viewport.height),
+        Some(Length::Em(v)) => Dimension::Points(v * font_size),
+        Some(Length::Auto) | None => Dimension::Auto,
+    }
+}
+
+/// Like `dimension`, for properties where unset means zero and `auto` is not allowed.
+fn length(value: Option<Length>, ctx: &LayoutContext, font_size: f32) -> LengthPercentage {
+    match dimension(value, ctx, font_size) {
+        Dimension::Percent(p) => LengthPercentage::Percent(p),
+        Dimension::Points(px) => LengthPercentage::Points(px),
+        Dimension::Auto => LengthPercentage::Points(0.0),
+    }
+}
+
+/// Like `dimension`, for margins: unset means zero, `auto` takes up free space.
+fn margin(value: Option<Length>, ctx: &LayoutContext, font_size: f32) -> LengthPercentageAuto {
+    match (value, dimension(value, ctx, font_size)) {
+        (None, _) => LengthPercentageAuto::Points(0.0),
+        (_, Dimension::Percent(p)) => LengthPercentageAuto::Percent(p),
+        (_, Dimension::Points(px)) => LengthPercentageAuto::Points(px),
+        (_, Dimension::Auto) => LengthPercentageAuto::Auto,
+    }
+}
+
+fn inset(value: Option<Length>, ctx: &LayoutContext, font_size: f32) -> LengthPercentageAuto {
+    match dimension(value, ctx, font_size) {
+        Dimension::Percent(p) => LengthPercentageAuto::Percent(p),
+        Dimension::Points(px) => LengthPercentageAuto::Points(px),
+        Dimension::Auto => LengthPercentageAuto::Auto,
+    }
+}
+
+fn track(track: &GridTrack, ctx: &LayoutContext, font_size: f32) -> NonRepeatedTrackSizingFunction {
+    match track {
+        GridTrack::Fr(fr) => flex(*fr),
+        GridTrack::Length(length) => match dimension(Some(*length), ctx, font_size) {
+            Dimension::Points(px) => points(px),
+            Dimension::Percent(p) => percent(p),
+            Dimension::Auto => auto(),
+        },
+        // Nested repeats are rejected when the scene is parsed.
+        GridTrack::Repeat(..) => auto(),
+    }
+}
+
+fn grid_template(template: Option<&GridTemplate>, ctx: &LayoutContext, font_size: f32) -> Vec<TrackSizingFunction> {
+    template.map(|t| t.0.iter().map(|entry| match entry {
+        GridTrack::Repeat(count, tracks) => repeat(*count, tracks.iter().map(|t| track(t, ctx, font_size)).collect()),
+        single => TrackSizingFunction::Single(track(single, ctx, font_size)),
+    }).collect()).unwrap_or_default()
+}
+
+fn grid_placement(lines: Option<GridLines>) -> Line<GridPlacement> {
+    let placement = |l| match l {
+        GridLine::Auto => GridPlacement::Auto,
+        GridLine::Index(i) => line(i),
+        GridLine::Span(n) => span(n),
+    };
+    lines.map(|l| Line { start: placement(l.start), end: placement(l.end) }).unwrap_or_default()
+}
+
+/// `justifyContent` / `alignContent` values; `None` leaves Taffy's default.
+fn align_content(value: Option<&str>) -> Option<AlignContent> {
+    Some(match value? {
+        "flexStart" => AlignContent::FlexStart,
+        "flexEnd" => AlignContent::FlexEnd,
+        "start" => AlignContent::Start,
+        "end" => AlignContent::End,
+        "center" => AlignContent::Center,
+        "stretch" => AlignContent::Stretch,
+        "spaceBetween" => AlignContent::SpaceBetween,
+        "spaceAround" => AlignContent::SpaceAround,
+        "spaceEvenly" => AlignContent::SpaceEvenly,
+        _ => return None,
+    })
+}
+
+/// `alignItems` / `alignSelf` values; `None` leaves Taffy's default.
+fn align_items(value: Option<&str>) -> Option<AlignItems> {
+    Some(match value? {
+        "flexStart" => AlignItems::FlexStart,
+        "flexEnd" => AlignItems::FlexEnd,
+        "start" => AlignItems::Start,
+        "end" => AlignItems::End,
+        "center" => AlignItems::Center,
+        "baseline" => AlignItems::Baseline,
+        "stretch" => AlignItems::Stretch,
+        _ => return None,
+    })
+}
+
+/// The Taffy style of `node`, whose own text style (after inheritance) is `text`.
+pub fn node_style(node: &SceneNode, ctx: &LayoutContext, text: &TextStyle) -> Style {
+    let em = text.font_size();
+    let mut w = dimension(node.style.width, ctx, em);
+    let mut h = dimension(node.style.height, ctx, em);
 
     if node.tag == "image" {
         if let Some(src) = &node.src {
-            if let Some(pixmap) = assets.get(src) {
+            if let Some(pixmap) = ctx.assets.get(src) {
                 if matches!(w, Dimension::Auto) { w = Dimension::Points(pixmap.width() as f32); }
                 if matches!(h, Dimension::Auto) { h = Dimension::Points(pixmap.height() as f32); }
             }
         }
     }
 
-    let style = Style {
+    let grid = node.style.display.as_deref() == Some("grid");
+    Style {
+        display: if grid { Display::Grid } else { Display::Flex },
+        grid_template_columns: grid_template(node.style.grid_template_columns.as_ref(), ctx, em),
+        grid_template_rows: grid_template(node.style.grid_template_rows.as_ref(), ctx, em),
+        grid_column: grid_placement(node.style.grid_column),
+        grid_row: grid_placement(node.style.grid_row),
         size: Size { width: w, height: h },
         aspect_ratio: node.style.aspect_ratio,
         position: match node.style.position.as_deref() { Some("absolute") => Position::Absolute, _ => Position::Relative },
+        min_size: Size { width: dimension(node.style.min_width, ctx, em), height: dimension(node.style.min_height, ctx, em) },
+        max_size: Size { width: dimension(node.style.max_width, ctx, em), height: dimension(node.style.max_height, ctx, em) },
         flex_grow: node.style.flex.unwrap_or(0.0),
-        flex_direction: match node.style.flex_direction.as_deref() { Some("column") => FlexDirection::Column, _ => FlexDirection::Row },
-        justify_content: Some(match node.style.justify_content.as_deref() { Some("center") => JustifyContent::Center, Some("spaceBetween") => JustifyContent::SpaceBetween, Some("flexEnd") => JustifyContent::FlexEnd, _ => JustifyContent::FlexStart }),
-        align_items: Some(match node.style.align_items.as_deref() { Some("center") => AlignItems::Center, Some("flexEnd") => AlignItems::FlexEnd, _ => AlignItems::FlexStart }),
+        flex_shrink: node.style.flex_shrink.unwrap_or(1.0),
+        flex_basis: dimension(node.style.flex_basis, ctx, em),
+        flex_direction: match node.style.flex_direction.as_deref() {
+            Some("column") => FlexDirection::Column,
+            Some("rowReverse") => FlexDirection::RowReverse,
+            Some("columnReverse") => FlexDirection::ColumnReverse,
+            _ => FlexDirection::Row,
+        },
+        flex_wrap: match node.style.flex_wrap.as_deref() { Some("wrap") => FlexWrap::Wrap, Some("wrapReverse") => FlexWrap::WrapReverse, _ => FlexWrap::NoWrap },
+        justify_content: Some(align_content(node.style.justify_content.as_deref()).unwrap_or(JustifyContent::FlexStart)),
+        align_content: align_content(node.style.align_content.as_deref()),
+        // Grid cells stretch their items by default, as in CSS.
+        align_items: align_items(node.style.align_items.as_deref()).or((!grid).then_some(AlignItems::FlexStart)),
+        align_self: align_items(node.style.align_self.as_deref()),
+        gap: Size {
+            width: length(node.style.column_gap.or(node.style.gap), ctx, em),
+            height: length(node.style.row_gap.or(node.style.gap), ctx, em),
+        },
         padding: Rect {
-            left: LengthPercentage::Points(node.style.padding.unwrap_or(0.0)),
-            right: LengthPercentage::Points(node.style.padding.unwrap_or(0.0)),
-            top: LengthPercentage::Points(node.style.padding.unwrap_or(0.0)),
-            bottom: LengthPercentage::Points(node.style.padding.unwrap_or(0.0)),
+            left: length(node.style.padding, ctx, em),
+            right: length(node.style.padding, ctx, em),
+            top: length(node.style.padding, ctx, em),
+            bottom: length(node.style.padding, ctx, em),
         },
         margin: Rect {
-            left: LengthPercentageAuto::Points(node.style.margin_left.or(node.style.margin).unwrap_or(0.0)),
-            right: LengthPercentageAuto::Points(node.style.margin_right.or(node.style.margin).unwrap_or(0.0)),
-            top: LengthPercentageAuto::Points(node.style.margin_top.or(node.style.margin).unwrap_or(0.0)),
-            bottom: LengthPercentageAuto::Points(node.style.margin_bottom.or(node.style.margin).unwrap_or(0.0)),
+            left: margin(node.style.margin_left.or(node.style.margin), ctx, em),
+            right: margin(node.style.margin_right.or(node.style.margin), ctx, em),
+            top: margin(node.style.margin_top.or(node.style.margin), ctx, em),
+            bottom: margin(node.style.margin_bottom.or(node.style.margin), ctx, em),
         },
         inset: Rect {
-            left: node.style.left.map(LengthPercentageAuto::Points).unwrap_or(LengthPercentageAuto::Auto),
-            right: node.style.right.map(LengthPercentageAuto::Points).unwrap_or(LengthPercentageAuto::Auto),
-            top: node.style.top.map(LengthPercentageAuto::Points).unwrap_or(LengthPercentageAuto::Auto),
-            bottom: node.style.bottom.map(LengthPercentageAuto::Points).unwrap_or(LengthPercentageAuto::Auto),
+            left: inset(node.style.left, ctx, em),
+            right: inset(node.style.right, ctx, em),
+            top: inset(node.style.top, ctx, em),
+            bottom: inset(node.style.bottom, ctx, em),
         },
         ..Default::default()
-    };
+    }
+}
+
+/// Builds the Taffy tree for `node`. `inherited` is its parent's text style,
+/// which text is measured with and `em` units are relative to, unless the
+/// node overrides it.
+pub fn build_taffy(
+    taffy: &mut Taffy, 
+    node: &SceneNode, 
+    ctx: &LayoutContext,
+    inherited: &TextStyle,
+) -> Result<Node, RenderError> {
+    let text = inherited.inherit(&node.style);
+    let style = node_style(node, ctx, &text);
 
     if let Some(text_content_ref) = &node.text {
-        let font_name = node.style.font_family.as_deref().unwrap_or("default");
-        let font_opt = fonts.get(font_name).or_else(|| fonts.values().next()).cloned();
+        let font_name = text.font_family.unwrap_or("default");
+        let font_opt = ctx.fonts.get(font_name).or_else(|| ctx.fonts.values().next()).cloned();
         
         if let Some(font) = font_opt {
             let text_content = String::from(text_content_ref);
-            let font_size = node.style.font_size.unwrap_or(32.0);
-            let letter_spacing = node.style.letter_spacing.unwrap_or(0.0);
-            let line_height = node.style.line_height.unwrap_or(font_size * 1.2);
+            let font_size = text.font_size();
+            let letter_spacing = text.letter_spacing();
+            let line_height = text.line_height();
 
-            return taffy.new_leaf_with_measure(style, MeasureFunc::Boxed(Box::new(move |_known_dims, available_space| {
+            return Ok(taffy.new_leaf_with_measure(style, MeasureFunc::Boxed(Box::new(move |_known_dims, available_space| {
                 let max_width = match available_space.width {
                     AvailableSpace::Definite(px) => Some(px),
                     AvailableSpace::MinContent => Some(0.0),
@@ -78,13 +218,13 @@ pub fn build_taffy(
                 let height = lines.len() as f32 * line_height;
 
                 Size { width, height }
-            }))).unwrap();
+            })))?);
         }
     }
 
     let mut child_ids = vec![];
     if let Some(children) = &node.children {
-        for child in children { child_ids.push(build_taffy(taffy, child, assets, fonts)); }
+        for child in children { child_ids.push(build_taffy(taffy, child, ctx, &text)?); }
     }
-    taffy.new_with_children(style, &child_ids).unwrap()
+    Ok(taffy.new_with_children(style, &child_ids)?)
 }
\ No newline at end of file
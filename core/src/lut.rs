@@ -0,0 +1,166 @@
+use tiny_skia::Pixmap;
+
+/// A color lookup table read from a `.cube` file.
+pub struct Lut {
+    /// Entries per axis.
+    size: usize,
+    three_d: bool,
+    /// `size` entries for a 1D table; `size`³ for a 3D one, red varying fastest.
+    table: Vec<[f32; 3]>,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+}
+
+/// How a 3D table is sampled between its entries.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LutInterpolation {
+    /// Blends the four entries of the tetrahedron around the color. Keeps
+    /// neutral grays on the table's diagonal.
+    Tetrahedral,
+    /// Blends the eight entries of the cube around the color.
+    Trilinear,
+}
+
+impl LutInterpolation {
+    pub fn parse(name: Option<&str>) -> LutInterpolation {
+        match name {
+            Some("trilinear") => LutInterpolation::Trilinear,
+            _ => LutInterpolation::Tetrahedral,
+        }
+    }
+}
+
+impl Lut {
+    /// Parses the text of a `.cube` file (Adobe/Resolve format) holding a 1D or a 3D table.
+    pub fn parse(text: &str) -> Result<Lut, String> {
+        let mut size_1d = None;
+        let mut size_3d = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut table = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let at = |message: String| format!("line {}: {}", i + 1, message);
+            let mut words = line.split_whitespace();
+            let keyword = words.next().unwrap_or_default();
+            let numbers = || -> Result<Vec<f32>, String> {
+                line.split_whitespace().skip(1).map(|w| w.parse::<f32>().map_err(|_| at(format!("'{}' is not a number", w)))).collect()
+            };
+
+            match keyword {
+                "TITLE" => {}
+                "LUT_1D_SIZE" | "LUT_3D_SIZE" => {
+                    let size = words.next().and_then(|w| w.parse::<usize>().ok()).ok_or_else(|| at(format!("{} needs a whole number", keyword)))?;
+                    let (slot, max) = if keyword == "LUT_1D_SIZE" { (&mut size_1d, 65536) } else { (&mut size_3d, 256) };
+                    if !(2..=max).contains(&size) { return Err(at(format!("{} must be between 2 and {}", keyword, max))); }
+                    *slot = Some(size);
+                }
+                "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                    let values: [f32; 3] = numbers()?.try_into().map_err(|_| at(format!("{} needs three numbers", keyword)))?;
+                    if keyword == "DOMAIN_MIN" { domain_min = values } else { domain_max = values }
+                }
+                // Resolve's form of the domain, the same for all three channels.
+                "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                    let [min, max]: [f32; 2] = numbers()?.try_into().map_err(|_| at(format!("{} needs two numbers", keyword)))?;
+                    (domain_min, domain_max) = ([min; 3], [max; 3]);
+                }
+                _ if keyword.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) => {
+                    let values: Vec<f32> = line.split_whitespace().map(|w| w.parse::<f32>().map_err(|_| at(format!("'{}' is not a number", w)))).collect::<Result<_, _>>()?;
+                    let entry: [f32; 3] = values.try_into().map_err(|_| at("table entries need three numbers".to_string()))?;
+                    table.push(entry);
+                }
+                // Other keywords are tool-specific metadata.
+                _ => {}
+            }
+        }
+
+        let (size, three_d) = match (size_1d, size_3d) {
+            (Some(size), None) => (size, false),
+            (None, Some(size)) => (size, true),
+            (None, None) => return Err("missing LUT_1D_SIZE or LUT_3D_SIZE".to_string()),
+            (Some(_), Some(_)) => return Err("a file with both a 1D and a 3D table is not supported".to_string()),
+        };
+        let expected = if three_d { size * size * size } else { size };
+        if table.len() != expected {
+            return Err(format!("expected {} table entries, found {}", expected, table.len()));
+        }
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            return Err("DOMAIN_MAX must be greater than DOMAIN_MIN".to_string());
+        }
+        Ok(Lut { size, three_d, table, domain_min, domain_max })
+    }
+
+    /// Grades `pixmap` in place, mixing `intensity` (0-1) of the graded color
+    /// into the original. Colors are looked up un-premultiplied.
+    pub fn apply(&self, pixmap: &mut Pixmap, intensity: f32, interpolation: LutInterpolation) {
+        let intensity = intensity.clamp(0.0, 1.0);
+        if intensity == 0.0 { return; }
+        for p in pixmap.data_mut().chunks_exact_mut(4) {
+            if p[3] == 0 { continue; }
+            let a = p[3] as f32 / 255.0;
+            let color: [f32; 3] = std::array::from_fn(|c| {
+                let v = p[c] as f32 / 255.0 / a;
+                ((v - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c])).clamp(0.0, 1.0)
+            });
+            let graded = if self.three_d { self.sample_3d(color, interpolation) } else { self.sample_1d(color) };
+            for c in 0..3 {
+                let original = p[c] as f32 / 255.0 / a;
+                let v = original + (graded[c] - original) * intensity;
+                p[c] = (v.clamp(0.0, 1.0) * a * 255.0).round() as u8;
+            }
+        }
+    }
+
+    /// The lower of the two entries around `v` (0-1) and how far past it `v` lies.
+    fn cell(&self, v: f32) -> (usize, f32) {
+        let scaled = v * (self.size - 1) as f32;
+        let i = (scaled.floor() as usize).min(self.size - 2);
+        (i, scaled - i as f32)
+    }
+
+    fn sample_1d(&self, color: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|c| {
+            let (i, t) = self.cell(color[c]);
+            let (lo, hi) = (self.table[i][c], self.table[i + 1][c]);
+            lo + (hi - lo) * t
+        })
+    }
+
+    fn sample_3d(&self, [r, g, b]: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        let ((r0, fr), (g0, fg), (b0, fb)) = (self.cell(r), self.cell(g), self.cell(b));
+        let n = self.size;
+        let at = |dr: usize, dg: usize, db: usize| self.table[(r0 + dr) + (g0 + dg) * n + (b0 + db) * n * n];
+        let (c000, c111) = (at(0, 0, 0), at(1, 1, 1));
+
+        match interpolation {
+            LutInterpolation::Trilinear => {
+                let (c100, c010, c001) = (at(1, 0, 0), at(0, 1, 0), at(0, 0, 1));
+                let (c110, c101, c011) = (at(1, 1, 0), at(1, 0, 1), at(0, 1, 1));
+                let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+                std::array::from_fn(|c| {
+                    let g0b0 = lerp(c000[c], c100[c], fr);
+                    let g1b0 = lerp(c010[c], c110[c], fr);
+                    let g0b1 = lerp(c001[c], c101[c], fr);
+                    let g1b1 = lerp(c011[c], c111[c], fr);
+                    lerp(lerp(g0b0, g1b0, fg), lerp(g0b1, g1b1, fg), fb)
+                })
+            }
+            LutInterpolation::Tetrahedral => {
+                // Walk from c000 to c111 along the edges in order of the largest
+                // fraction first; each step adds that edge's difference.
+                let (first, second, weights) = if fr > fg {
+                    if fg > fb { (at(1, 0, 0), at(1, 1, 0), [fr, fg, fb]) }
+                    else if fr > fb { (at(1, 0, 0), at(1, 0, 1), [fr, fb, fg]) }
+                    else { (at(0, 0, 1), at(1, 0, 1), [fb, fr, fg]) }
+                } else if fb > fg { (at(0, 0, 1), at(0, 1, 1), [fb, fg, fr]) }
+                else if fb > fr { (at(0, 1, 0), at(0, 1, 1), [fg, fb, fr]) }
+                else { (at(0, 1, 0), at(1, 1, 0), [fg, fr, fb]) };
+                std::array::from_fn(|c| {
+                    c000[c] + (first[c] - c000[c]) * weights[0] + (second[c] - first[c]) * weights[1] + (c111[c] - second[c]) * weights[2]
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use tiny_skia::*;
+use crate::error::RenderError;
+use crate::filters::{apply_filters, EdgeMode, FilterRegion};
+use crate::types::SceneNode;
+use super::primitives::fill_rule;
+use super::{filter_margin, new_layer, NodeBox, SceneContext, Surface};
+
+pub fn has_backdrop(node: &SceneNode) -> bool {
+    node.style.backdrop_filter.as_ref().is_some_and(|ops| !ops.is_empty())
+}
+
+/// Paints what `backdrop` already holds under `node`, run through the node's
+/// `backdrop_filter` and clipped to its shape, into the node's `layer`. The
+/// layer's own opacity, filters and mask then apply to it like to the rest of
+/// the node.
+///
+/// Only what was drawn into `backdrop` is seen: a layered ancestor hides
+/// everything below itself, as a CSS backdrop root does.
+pub fn draw_backdrop(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: &Path, backdrop: &Surface, layer: &mut Surface) -> Result<(), RenderError> {
+    let Some(ops) = node.style.backdrop_filter.as_deref() else { return Ok(()) };
+    let Some(clip) = layer.path_mask(path, fill_rule(node), node_box.transform) else { return Ok(()) };
+
+    // Blurs need the backdrop around the shape too, as far as they reach.
+    let reach = filter_margin(ctx, ops).ceil() as i32;
+    let (lx, ly) = layer.origin;
+    let (bx, by) = backdrop.origin;
+    let wanted = IntRect::from_xywh(lx - reach, ly - reach, layer.pixmap.width() + 2 * reach as u32, layer.pixmap.height() + 2 * reach as u32);
+    let available = IntRect::from_xywh(bx, by, backdrop.pixmap.width(), backdrop.pixmap.height());
+    let Some(area) = wanted.and_then(|w| available.and_then(|a| a.intersect(&w))) else { return Ok(()) };
+
+    let mut sample = new_layer(ctx, area.width(), area.height())?;
+    let (stride, row) = (backdrop.pixmap.width() as usize * 4, area.width() as usize * 4);
+    let src = backdrop.pixmap.as_ref();
+    for (y, dst) in sample.data_mut().chunks_exact_mut(row).enumerate() {
+        let start = (area.y() - by) as usize * stride + y * stride + (area.x() - bx) as usize * 4;
+        dst.copy_from_slice(&src.data()[start..start + row]);
+    }
+    // Past the backdrop's edges, CSS repeats the edge pixels.
+    let region = FilterRegion { origin: (area.x(), area.y()), transform: node_box.transform, width: node_box.w, height: node_box.h };
+    apply_filters(&mut sample, ops, &region, EdgeMode::Clamp, &mut ctx.engine.blur_scratch.borrow_mut());
+
+    layer.pixmap.draw_pixmap(area.x() - lx, area.y() - ly, sample.as_ref(), &PixmapPaint::default(), Transform::identity(), Some(&clip));
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use serde::Serialize;
+use taffy::prelude::{Node, Taffy};
+use crate::engine::EngineCore;
+use crate::error::RenderError;
+use crate::text::compute_text_lines;
+use crate::types::SceneNode;
+use super::{node_box, text::line_offset, NodeBox};
+
+/// Where layout put a node, as returned by `EngineCore::compute_layout`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutBox {
+    pub id: Option<String>,
+    pub tag: String,
+    /// Index of the parent's box in the list; `None` for the root.
+    pub parent: Option<usize>,
+    /// The layout box in canvas coordinates, before any transform.
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    /// `[a, b, c, d, e, f]` mapping node-local points (origin at the box's
+    /// top-left) to the canvas, with every ancestor's transform applied:
+    /// `(a·x + c·y + e, b·x + d·y + f)`. 3D rotations are not included.
+    pub transform: [f32; 6],
+    /// Text lines as drawn, in node-local coordinates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<Vec<LineBox>>,
+}
+
+/// One line of a text node's text after wrapping and alignment.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LineBox {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Boxes of `node` and its descendants in document order, parents first.
+pub fn layout_boxes(taffy: &Taffy, engine: &EngineCore, node: &SceneNode, layout_id: Node) -> Result<Vec<LayoutBox>, RenderError> {
+    let mut boxes = vec![];
+    collect(taffy, engine, node, layout_id, &NodeBox::canvas(), None, &mut boxes)?;
+    Ok(boxes)
+}
+
+fn collect<'a>(
+    taffy: &Taffy, engine: &EngineCore, node: &'a SceneNode, layout_id: Node, parent: &NodeBox<'a>, parent_index: Option<usize>, boxes: &mut Vec<LayoutBox>
+) -> Result<(), RenderError> {
+    let node_box = node_box(taffy, node, layout_id, parent)?;
+    let t = node_box.transform;
+    let index = boxes.len();
+    boxes.push(LayoutBox {
+        id: node.id.clone(),
+        tag: node.tag.clone(),
+        parent: parent_index,
+        x: node_box.x,
+        y: node_box.y,
+        width: node_box.w,
+        height: node_box.h,
+        transform: [t.sx, t.ky, t.kx, t.sy, t.tx, t.ty],
+        lines: node.text.as_deref().and_then(|text| line_boxes(engine, &node_box, text)),
+    });
+
+    if let (Ok(child_ids), Some(children)) = (taffy.children(layout_id), &node.children) {
+        for (child, &cid) in children.iter().zip(child_ids.iter()) {
+            collect(taffy, engine, child, cid, &node_box, Some(index), boxes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Mirrors the line placement in `draw_text`.
+fn line_boxes(engine: &EngineCore, node_box: &NodeBox, text_content: &str) -> Option<Vec<LineBox>> {
+    let text = &node_box.text;
+    let font = engine.fonts.get(text.font_family.unwrap_or("default")).or_else(|| engine.fonts.values().next())?;
+    let w = node_box.w;
+    let wrap_width = if w > 0.0 { Some(w) } else { None };
+    let lh = text.line_height();
+    let lines = compute_text_lines(font, text_content, text.font_size(), text.letter_spacing(), wrap_width);
+    Some(lines.iter().enumerate().map(|(i, line)| LineBox {
+        text: line.chars.iter().map(|(c, _)| c).collect(),
+        x: line_offset(text.text_align, w, line.width),
+        y: i as f32 * lh,
+        width: line.width,
+        height: lh,
+    }).collect())
+}
@@ -1,11 +1,13 @@
 use tiny_skia::*;
 use crate::types::SceneNode;
 use crate::engine::EngineCore;
+use crate::error::RenderWarning;
+use super::Surface;
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_image(
-    pixmap: &mut Pixmap, 
+    pixmap: &mut Surface, 
     node: &SceneNode, 
-    _path: &Path, 
     transform: Transform, 
     engine: &EngineCore,
     w: f32, 
@@ -13,16 +15,16 @@ pub fn draw_image(
     opacity: f32
 ) {
     if let Some(src) = &node.src {
-        if let Some(img_pixmap) = engine.assets.get(src) {
-            let mut paint = PixmapPaint::default();
-            paint.opacity = opacity;
-            paint.quality = FilterQuality::Bilinear;
+        let Some(img_pixmap) = engine.assets.get(src) else {
+            engine.warn(RenderWarning::MissingAsset { src: src.clone() });
+            return;
+        };
+        let paint = PixmapPaint { opacity, quality: FilterQuality::Bilinear, ..PixmapPaint::default() };
 
-            let sx = w / img_pixmap.width() as f32;
-            let sy = h / img_pixmap.height() as f32;
-            let ts = transform.pre_scale(sx, sy);
+        let sx = w / img_pixmap.width() as f32;
+        let sy = h / img_pixmap.height() as f32;
+        let ts = transform.pre_scale(sx, sy);
 
-            pixmap.draw_pixmap(0, 0, img_pixmap.as_ref(), &paint, ts, None);
-        }
+        pixmap.draw_pixmap(img_pixmap.as_ref(), &paint, ts, None);
     }
 }
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+use std::ops::{Deref, DerefMut};
+use tiny_skia::{IntSize, Pixmap};
+use crate::error::RenderError;
+
+/// How many released buffers the pool holds on to.
+const MAX_POOLED: usize = 16;
+
+/// Layers larger than this fail with `PixmapAllocation` rather than taking
+/// the process down.
+const MAX_LAYER_BYTES: usize = 1 << 30;
+
+/// Pixel buffers of released layers, reused by later layers (and frames) so
+/// that compositing does not allocate once the pool has warmed up.
+#[derive(Default)]
+pub struct LayerPool {
+    free: RefCell<Vec<Vec<u8>>>,
+}
+
+impl LayerPool {
+    /// A transparent `width`x`height` pixmap, returned to the pool when dropped.
+    pub fn layer(&self, width: u32, height: u32) -> Result<Layer<'_>, RenderError> {
+        let error = RenderError::PixmapAllocation { width, height };
+        let size = IntSize::from_wh(width, height).ok_or(error.clone())?;
+        let len = (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(4))
+            .filter(|&len| len <= MAX_LAYER_BYTES).ok_or(error.clone())?;
+
+        let mut free = self.free.borrow_mut();
+        // The smallest buffer that fits, or else the largest, which grows the least.
+        let fits = free.iter().enumerate().filter(|(_, b)| b.capacity() >= len).min_by_key(|(_, b)| b.capacity());
+        let pick = fits.or_else(|| free.iter().enumerate().max_by_key(|(_, b)| b.capacity())).map(|(i, _)| i);
+        let mut data = pick.map(|i| free.swap_remove(i)).unwrap_or_default();
+        drop(free);
+
+        data.clear();
+        data.try_reserve_exact(len).map_err(|_| error.clone())?;
+        data.resize(len, 0);
+        let pixmap = Pixmap::from_vec(data, size).ok_or(error)?;
+        Ok(Layer { pixmap: Some(pixmap), pool: self })
+    }
+
+    fn release(&self, data: Vec<u8>) {
+        let mut free = self.free.borrow_mut();
+        if free.len() < MAX_POOLED {
+            free.push(data);
+        } else if let Some(smallest) = free.iter_mut().min_by_key(|b| b.capacity()).filter(|b| b.capacity() < data.capacity()) {
+            *smallest = data;
+        }
+    }
+}
+
+/// A pixmap borrowed from a `LayerPool`.
+pub struct Layer<'a> {
+    /// Only taken on drop.
+    pixmap: Option<Pixmap>,
+    pool: &'a LayerPool,
+}
+
+impl Deref for Layer<'_> {
+    type Target = Pixmap;
+
+    fn deref(&self) -> &Pixmap {
+        self.pixmap.as_ref().expect("layer is live until dropped")
+    }
+}
+
+impl DerefMut for Layer<'_> {
+    fn deref_mut(&mut self) -> &mut Pixmap {
+        self.pixmap.as_mut().expect("layer is live until dropped")
+    }
+}
+
+impl Drop for Layer<'_> {
+    fn drop(&mut self) {
+        if let Some(pixmap) = self.pixmap.take() {
+            self.pool.release(pixmap.take());
+        }
+    }
+}
@@ -1,41 +1,57 @@
-use tiny_skia::{Pixmap, Mask, Color};
+use tiny_skia::{Mask, Transform};
 use taffy::prelude::*;
+use crate::error::RenderError;
 use crate::types::SceneNode;
-use crate::engine::EngineCore;
-use crate::render::draw_scene;
+use crate::layout::LayoutContext;
+use crate::text::TextStyle;
+use crate::render::{draw_scene, NodeBox, SceneContext, Surface};
 
-pub fn draw_mask_node(node: &SceneNode, width: u32, height: u32, engine: &EngineCore, mode: &str) -> Option<Mask> {
-    let mut mask_pixmap = Pixmap::new(width, height)?;
-    mask_pixmap.fill(Color::TRANSPARENT);
+/// Renders a mask node into a `Mask` matching `target`'s size and canvas position,
+/// ready to pass to `draw_pixmap` on `target`. `base` maps the canvas-sized
+/// layout of the mask onto the canvas; `text` is the masked node's text style,
+/// which the mask inherits.
+pub fn draw_mask_node<'a>(
+    ctx: &SceneContext, node: &'a SceneNode, target: &Surface, base: Transform, mode: &str, text: TextStyle<'a>
+) -> Result<Option<Mask>, RenderError> {
+    let engine = ctx.engine;
+    let (width, height) = (target.pixmap.width(), target.pixmap.height());
+    let mut mask_pixmap = engine.layers.layer(width, height)?;
 
     let mut taffy = Taffy::new();
     
     // Build the mask node's taffy handle
-    let mask_id = crate::layout::build_taffy(&mut taffy, node, &engine.assets, &engine.fonts);
+    let layout_ctx = LayoutContext {
+        assets: &engine.assets,
+        fonts: &engine.fonts,
+        viewport: Size { width: ctx.canvas_width as f32, height: ctx.canvas_height as f32 },
+    };
+    let mask_id = crate::layout::build_taffy(&mut taffy, node, &layout_ctx, &text)?;
     
-    // Create a virtual root container that fills the entire parent area.
+    // Create a virtual root container that fills the entire canvas.
     // This allows the 'mask_id' node to respect its own margins/positioning
-    // relative to the parent's size.
+    // relative to the canvas size.
     let root_style = Style {
         size: Size { 
-            width: Dimension::Points(width as f32), 
-            height: Dimension::Points(height as f32) 
+            width: Dimension::Points(ctx.canvas_width as f32), 
+            height: Dimension::Points(ctx.canvas_height as f32) 
         },
         ..Default::default()
     };
-    let root = taffy.new_with_children(root_style, &[mask_id]).ok()?;
+    let root = taffy.new_with_children(root_style, &[mask_id])?;
 
     let available_size = Size { 
-        width: AvailableSpace::Definite(width as f32), 
-        height: AvailableSpace::Definite(height as f32) 
+        width: AvailableSpace::Definite(ctx.canvas_width as f32), 
+        height: AvailableSpace::Definite(ctx.canvas_height as f32) 
     };
 
-    taffy.compute_layout(root, available_size).ok()?;
+    taffy.compute_layout(root, available_size)?;
     
     // Draw starting from the virtual root (at 0,0)
-    draw_scene(&taffy, node, mask_id, &mut mask_pixmap, engine, 0.0, 0.0, 1.0);
+    let mask_ctx = SceneContext { taffy: &taffy, engine, canvas_width: ctx.canvas_width, canvas_height: ctx.canvas_height };
+    let root_box = NodeBox { transform: base, text, ..NodeBox::canvas() };
+    draw_scene(&mask_ctx, node, mask_id, &mut Surface::new(mask_pixmap.as_mut(), target.origin), &root_box)?;
     
-    let mut mask = Mask::new(width, height)?;
+    let Some(mut mask) = Mask::new(width, height) else { return Ok(None) };
     let data = mask.data_mut();
     let src = mask_pixmap.data();
     
@@ -59,5 +75,5 @@ pub fn draw_mask_node(node: &SceneNode, width: u32, height: u32, engine: &Engine
 
         data[i] = if is_inverted { 255 - alpha } else { alpha };
     }
-    Some(mask)
+    Ok(Some(mask))
 }
@@ -2,104 +2,458 @@ mod primitives;
 mod images;
 mod text;
 mod mask;
+mod surface;
+mod shadow;
+mod transform;
+mod layer;
+mod boxes;
+mod backdrop;
 
 use tiny_skia::*;
-use taffy::prelude::*;
-use svgtypes::PathParser;
-use crate::types::SceneNode;
-use crate::utils::parse_blend_mode;
+use taffy::prelude::{Node, Taffy};
+use crate::error::{RenderError, RenderWarning};
+use crate::lut::LutInterpolation;
+use crate::types::{FilterOp, SceneNode};
+use crate::filters::{apply_filters, filter_ops, has_filters, EdgeMode, FilterRegion, MAX_SIGMA};
+use crate::utils::{parse_blend_mode, parse_svg_path};
 use crate::engine::EngineCore;
 
 pub use primitives::draw_primitive;
 pub use images::draw_image;
 pub use text::draw_text;
 pub use mask::draw_mask_node;
+pub use surface::Surface;
+pub use layer::{Layer, LayerPool};
+pub use boxes::{layout_boxes, LayoutBox, LineBox};
+use backdrop::{draw_backdrop, has_backdrop};
+use shadow::{draw_shadow, Shadow};
+use transform::Projection;
+use crate::text::TextStyle;
 
-pub fn generate_path(node: &SceneNode, w: f32, h: f32) -> Path {
+/// Corner radii in `[top_left, top_right, bottom_right, bottom_left]` order.
+/// Like CSS, all four shrink by the same factor when neighbours would overlap.
+fn corner_radii(node: &SceneNode, w: f32, h: f32) -> [f32; 4] {
+    let s = &node.style;
+    let base = s.border_radius.unwrap_or(0.0);
+    let radii = [s.border_top_left_radius, s.border_top_right_radius, s.border_bottom_right_radius, s.border_bottom_left_radius]
+        .map(|r| r.unwrap_or(base).max(0.0));
+    let [tl, tr, br, bl] = radii;
+
+    let fit = |len: f32, sum: f32| if sum > len { len / sum } else { 1.0 };
+    let scale = fit(w, tl + tr).min(fit(w, bl + br)).min(fit(h, tl + bl)).min(fit(h, tr + br)).max(0.0);
+    radii.map(|r| r * scale)
+}
+
+fn has_radius(node: &SceneNode) -> bool {
+    let s = &node.style;
+    [s.border_radius, s.border_top_left_radius, s.border_top_right_radius, s.border_bottom_right_radius, s.border_bottom_left_radius]
+        .iter().any(|r| r.unwrap_or(0.0) > 0.0)
+}
+
+fn push_rounded_rect(pb: &mut PathBuilder, w: f32, h: f32, [tl, tr, br, bl]: [f32; 4]) {
+    // Distance of the cubic control points from the corner's end points for a quarter circle.
+    const K: f32 = 1.0 - 0.552_284_8;
+    pb.move_to(tl, 0.0);
+    pb.line_to(w - tr, 0.0);
+    if tr > 0.0 { pb.cubic_to(w - tr * K, 0.0, w, tr * K, w, tr); }
+    pb.line_to(w, h - br);
+    if br > 0.0 { pb.cubic_to(w, h - br * K, w - br * K, h, w - br, h); }
+    pb.line_to(bl, h);
+    if bl > 0.0 { pb.cubic_to(bl * K, h, 0.0, h - bl * K, 0.0, h - bl); }
+    pb.line_to(0.0, tl);
+    if tl > 0.0 { pb.cubic_to(0.0, tl * K, tl * K, 0.0, tl, 0.0); }
+    pb.close();
+}
+
+/// The node's outline in node-local coordinates, or `None` when it has no
+/// shape to draw: path data that is empty, invalid or has no segments, or an
+/// empty box.
+pub fn generate_path(node: &SceneNode, w: f32, h: f32) -> Option<Path> {
     let mut pb = PathBuilder::new();
     if let Some(d) = &node.d {
-        let mut cx = 0.0; let mut cy = 0.0;
-        for segment in PathParser::from(d.as_str()) {
-            match segment {
-                Ok(svgtypes::PathSegment::MoveTo { abs, x, y }) => { cx = if abs { x as f32 } else { cx + x as f32 }; cy = if abs { y as f32 } else { cy + y as f32 }; pb.move_to(cx, cy); },
-                Ok(svgtypes::PathSegment::LineTo { abs, x, y }) => { cx = if abs { x as f32 } else { cx + x as f32 }; cy = if abs { y as f32 } else { cy + y as f32 }; pb.line_to(cx, cy); },
-                Ok(svgtypes::PathSegment::ClosePath { .. }) => { pb.close(); },
-                _ => {}
-            }
-        }
+        return parse_svg_path(d);
     } else if let Some(rect) = tiny_skia::Rect::from_xywh(0.0, 0.0, w, h) {
         if node.tag == "circle" || node.tag == "ellipse" {
             pb.push_oval(rect);
+        } else if has_radius(node) {
+            push_rounded_rect(&mut pb, w, h, corner_radii(node, w, h));
         } else {
-            let r = node.style.border_radius.unwrap_or(0.0).min(w/2.0).min(h/2.0);
-            if r > 0.0 {
-                pb.move_to(r, 0.0); pb.line_to(w-r, 0.0); pb.quad_to(w, 0.0, w, r);
-                pb.line_to(w, h-r); pb.quad_to(w, h, w-r, h); pb.line_to(r, h);
-                pb.quad_to(0.0, h, 0.0, h-r); pb.line_to(0.0, r); pb.quad_to(0.0, 0.0, r, 0.0);
-                pb.close();
-            } else {
-                pb.push_rect(rect);
-            }
+            pb.push_rect(rect);
+        }
+    }
+    pb.finish()
+}
+
+fn new_layer<'a>(ctx: &SceneContext<'a>, width: u32, height: u32) -> Result<Layer<'a>, RenderError> {
+    ctx.engine.layers.layer(width, height)
+}
+
+/// Everything a node draw needs that stays the same for the whole tree.
+pub struct SceneContext<'a> {
+    pub taffy: &'a Taffy,
+    pub engine: &'a EngineCore,
+    pub canvas_width: u32,
+    pub canvas_height: u32,
+}
+
+/// A laid-out node: its size, where layout put it, and where it ends up drawn.
+#[derive(Clone, Copy)]
+pub struct NodeBox<'a> {
+    /// Untransformed canvas position, the sum of layout offsets down the tree.
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+    /// Maps node-local coordinates to canvas coordinates, including every ancestor's transform.
+    /// For a 3D-rotated node this is where it is drawn flat, before `projection`.
+    transform: Transform,
+    /// The node's `perspective`, which its 3D-rotated children are seen through.
+    perspective: Option<f32>,
+    projection: Option<Projection>,
+    /// Only set on `NodeBox::canvas()`: its child is the root stacking context.
+    canvas: bool,
+    /// Text properties inherited down to this node.
+    text: TextStyle<'a>,
+}
+
+impl NodeBox<'_> {
+    /// The parent of a root node: the canvas itself.
+    pub fn canvas() -> NodeBox<'static> {
+        NodeBox { x: 0.0, y: 0.0, w: 0.0, h: 0.0, transform: Transform::identity(), perspective: None, projection: None, canvas: true, text: TextStyle::default() }
+    }
+}
+
+fn node_box<'a>(taffy: &Taffy, node: &'a SceneNode, layout_id: Node, parent: &NodeBox<'a>) -> Result<NodeBox<'a>, RenderError> {
+    let layout = taffy.layout(layout_id)?;
+    let (lx, ly, w, h) = (layout.location.x, layout.location.y, layout.size.width, layout.size.height);
+
+    let local = transform::local_transform(&node.style, w, h);
+    let transform = parent.transform.pre_translate(lx, ly).pre_concat(local);
+
+    let parent_perspective = parent.perspective.map(|d| (d, parent.w / 2.0, parent.h / 2.0));
+    let placement = Transform::from_translate(lx, ly).pre_concat(local);
+    let projection = transform::projection(&node.style, w, h, placement, parent.transform, parent_perspective);
+
+    Ok(NodeBox { x: parent.x + lx, y: parent.y + ly, w, h, transform, perspective: node.style.perspective, projection, canvas: false, text: parent.text.inherit(&node.style) })
+}
+
+fn has_blend_mode(node: &SceneNode) -> bool {
+    parse_blend_mode(node.style.blend_mode.as_deref().unwrap_or("normal")) != BlendMode::SourceOver
+}
+
+fn is_clipped(node: &SceneNode) -> bool {
+    node.style.overflow.as_deref() == Some("hidden") || has_radius(node) || node.tag == "circle"
+}
+
+/// How far a Gaussian of `sigma` can carry pixels. `gaussian_blur` reaches about
+/// 3σ either side; the rest is headroom so a layer edge never cuts the tail off.
+fn blur_margin(sigma: f32) -> f32 {
+    if sigma > 0.0 { (sigma.min(MAX_SIGMA) * 4.0).ceil() + 2.0 } else { 0.0 }
+}
+
+/// How far `ops` can spread pixels beyond what they are applied to. Channel
+/// offsets count for no more than the canvas size, which keeps layers bounded;
+/// channels shifted in from further off the canvas are lost.
+fn filter_margin(ctx: &SceneContext, ops: &[FilterOp]) -> f32 {
+    let canvas = ctx.canvas_width.max(ctx.canvas_height) as f32;
+    ops.iter().map(|op| match op {
+        FilterOp::Blur { radius } => blur_margin(*radius),
+        FilterOp::ChromaticAberration { offset_x, offset_y } => offset_x.abs().max(offset_y.abs()).min(canvas).ceil() + 1.0,
+        _ => 0.0,
+    }).sum()
+}
+
+fn union(a: Option<Rect>, b: Option<Rect>) -> Option<Rect> {
+    match (a, b) {
+        (Some(a), Some(b)) => Rect::from_ltrb(a.left().min(b.left()), a.top().min(b.top()), a.right().max(b.right()), a.bottom().max(b.bottom())),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Node-local area the node's own fill, stroke, image and text can cover.
+fn local_bounds(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: Option<&Path>) -> Option<Rect> {
+    let (w, h) = (node_box.w, node_box.h);
+    let stroke = primitives::stroke_width(node);
+    // Miter joins can reach twice the stroke width past the outline.
+    let mut local = path.and_then(|p| p.bounds().outset(stroke * 2.0, stroke * 2.0));
+    if node.tag == "image" { local = union(local, Rect::from_xywh(0.0, 0.0, w, h)); }
+    if let Some(t) = &node.text { local = union(local, text::text_bounds(&node_box.text, t, ctx.engine, w, h)); }
+    local
+}
+
+/// Canvas-space area that a node and its subtree can touch, before its mask is applied.
+fn paint_bounds<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, parent: &NodeBox<'a>) -> Result<Option<Rect>, RenderError> {
+    let node_box = node_box(ctx.taffy, node, layout_id, parent)?;
+    let bounds = flat_bounds(ctx, node, layout_id, &node_box)?;
+    Ok(match &node_box.projection {
+        Some(projection) => bounds.map(|b| project_bounds(projection, b)),
+        None => bounds,
+    })
+}
+
+/// Like `paint_bounds`, but before a 3D-rotated node is projected.
+fn flat_bounds<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, node_box: &NodeBox<'a>) -> Result<Option<Rect>, RenderError> {
+    let margin = filter_margin(ctx, &filter_ops(&node.style));
+    Ok(unfiltered_bounds(ctx, node, layout_id, node_box)?.and_then(|b| b.outset(margin, margin)))
+}
+
+/// Like `flat_bounds`, but before the node's own filters spread it.
+fn unfiltered_bounds<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, node_box: &NodeBox<'a>) -> Result<Option<Rect>, RenderError> {
+    let path = generate_path(node, node_box.w, node_box.h);
+    let mut bounds = own_bounds(ctx, node, node_box, path.as_ref());
+
+    // Clipped children still count: cropping the layer would make tiny-skia chop
+    // their curves at the layer edge and change anti-aliasing inside the clip.
+    if let (Ok(child_ids), Some(children)) = (ctx.taffy.children(layout_id), &node.children) {
+        for (child, &cid) in children.iter().zip(child_ids.iter()) {
+            bounds = union(bounds, paint_bounds(ctx, child, cid, node_box)?);
+        }
+    }
+
+    // One extra pixel covers anti-aliasing.
+    Ok(bounds.and_then(|b| b.outset(1.0, 1.0)))
+}
+
+/// Canvas-space area of a node's own paint and drop shadow, without its children.
+fn own_bounds(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: Option<&Path>) -> Option<Rect> {
+    let local = local_bounds(ctx, node, node_box, path);
+    let bounds = local.and_then(|r| r.transform(node_box.transform));
+    match (Shadow::of(node), local) {
+        (Some(shadow), Some(local)) => union(bounds, shadow.bounds(local, node_box.transform)),
+        _ => bounds,
+    }
+}
+
+/// Where `flat` lands after `projection`. Anything reaching behind the viewer
+/// can end up anywhere, so it is given unbounded extent.
+fn project_bounds(projection: &Projection, flat: Rect) -> Rect {
+    let corners = [(flat.left(), flat.top()), (flat.right(), flat.top()), (flat.right(), flat.bottom()), (flat.left(), flat.bottom())];
+    let unbounded = Rect::from_ltrb(-1e7, -1e7, 1e7, 1e7).unwrap();
+    corners.iter()
+        .map(|&(x, y)| projection.map.map(x, y).and_then(|(x, y)| Rect::from_xywh(x, y, 0.0, 0.0)))
+        .try_fold(None, |acc, p| p.map(|p| union(acc, Some(p))))
+        .flatten()
+        .and_then(|r| r.outset(1.0, 1.0))
+        .unwrap_or(unbounded)
+}
+
+/// Whether `node` needs a layer of its own to composite: opacity, blend mode,
+/// clip, filter, backdrop filter, LUT, mask or 3D projection.
+fn is_layered(node: &SceneNode, node_box: &NodeBox) -> bool {
+    // Children always blend inside their parent's group, never with what is
+    // underneath it, so a child with a blend mode needs the parent's layer.
+    let isolates_children = node.children.iter().flatten().any(has_blend_mode);
+    node.style.opacity.unwrap_or(1.0) != 1.0 || has_blend_mode(node) || isolates_children
+        || is_clipped(node) || has_filters(&node.style) || has_backdrop(node) || node.style.lut.is_some() || node.mask.is_some() || node_box.projection.is_some()
+}
+
+/// Layered nodes and nodes with a `z_index` paint their subtree as a unit,
+/// like CSS stacking contexts.
+fn starts_context(node: &SceneNode, node_box: &NodeBox) -> bool {
+    node.style.z_index.is_some() || is_layered(node, node_box)
+}
+
+/// A descendant with a `z_index`, painted by its stacking context rather than its parent.
+struct Lifted<'a> {
+    node: &'a SceneNode,
+    layout_id: Node,
+    parent: NodeBox<'a>,
+    z_index: i32,
+}
+
+/// Collects the `z_index`ed nodes under `node` that belong to its stacking
+/// context, in tree order: nested contexts keep theirs.
+fn collect_lifted<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, node_box: &NodeBox<'a>, out: &mut Vec<Lifted<'a>>) -> Result<(), RenderError> {
+    let (Ok(child_ids), Some(children)) = (ctx.taffy.children(layout_id), &node.children) else { return Ok(()) };
+    for (child, &cid) in children.iter().zip(child_ids.iter()) {
+        if let Some(z_index) = child.style.z_index {
+            out.push(Lifted { node: child, layout_id: cid, parent: *node_box, z_index });
+            continue;
+        }
+        let child_box = self::node_box(ctx.taffy, child, cid, node_box)?;
+        if !is_layered(child, &child_box) {
+            collect_lifted(ctx, child, cid, &child_box, out)?;
         }
     }
-    pb.finish().unwrap_or_else(|| { let b = PathBuilder::new(); b.finish().unwrap() })
+    Ok(())
+}
+
+/// Draws a node's image, text and children.
+fn draw_content<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, node_box: &NodeBox<'a>, path: Option<&Path>, target: &mut Surface, context: bool) -> Result<(), RenderError> {
+    draw_own_content(ctx, node, node_box, path, target)?;
+    draw_children(ctx, node, layout_id, node_box, target, context)
 }
 
-pub fn draw_scene(
-    taffy: &Taffy, node: &SceneNode, layout_id: Node, pixmap: &mut Pixmap, 
-    engine: &EngineCore, parent_x: f32, parent_y: f32, parent_opacity: f32
-) {
-    let layout = taffy.layout(layout_id).unwrap();
-    let (x, y, w, h) = (parent_x + layout.location.x, parent_y + layout.location.y, layout.size.width, layout.size.height);
+/// Draws a node's image, text and inset shadow.
+fn draw_own_content(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: Option<&Path>, target: &mut Surface) -> Result<(), RenderError> {
+    let NodeBox { w, h, transform, .. } = *node_box;
 
-    let mut transform = Transform::from_translate(x, y);
-    transform = transform.pre_translate(w/2.0, h/2.0);
-    if let Some(r) = node.style.rotate { transform = transform.pre_rotate(r); }
-    if let Some(s) = node.style.scale { transform = transform.pre_scale(s, s); }
-    transform = transform.pre_translate(-w/2.0, -h/2.0);
+    if node.tag == "image" { draw_image(target, node, transform, ctx.engine, w, h, 1.0); }
+    if let Some(t) = &node.text { draw_text(target, node, &node_box.text, t, transform, ctx.engine, w, 1.0); }
+    if let (Some(shadow), Some(path)) = (Shadow::of(node).filter(|s| s.inset), path) {
+        draw_shadow(ctx, node, node_box, path, &shadow, target)?;
+    }
+    Ok(())
+}
+
+/// Draws a node's children. Children with a `z_index` are left to the
+/// stacking context, which paints them sorted by it (stable, so ties keep tree
+/// order): negative ones below its other children, the rest above.
+fn draw_children<'a>(ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, node_box: &NodeBox<'a>, target: &mut Surface, context: bool) -> Result<(), RenderError> {
+    let mut lifted = vec![];
+    if context { collect_lifted(ctx, node, layout_id, node_box, &mut lifted)?; }
+    lifted.sort_by_key(|l| l.z_index);
+    let (below, above) = lifted.split_at(lifted.partition_point(|l| l.z_index < 0));
 
-    let current_opacity = parent_opacity * node.style.opacity.unwrap_or(1.0);
+    for l in below { draw_scene(ctx, l.node, l.layout_id, target, &l.parent)?; }
+    if let (Ok(child_ids), Some(children)) = (ctx.taffy.children(layout_id), &node.children) {
+        for (child, &cid) in children.iter().zip(child_ids.iter()) {
+            if child.style.z_index.is_none() { draw_scene(ctx, child, cid, target, node_box)?; }
+        }
+    }
+    for l in above { draw_scene(ctx, l.node, l.layout_id, target, &l.parent)?; }
+    Ok(())
+}
+
+/// Paints a node that needs no layer of its own: its shadow, shape and content
+/// go through small layers composited the same way as the layered path's, so
+/// both paths give the same pixels.
+fn draw_own(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: Option<&Path>, target: &mut Surface) -> Result<(), RenderError> {
+    let NodeBox { w, h, transform, .. } = *node_box;
+    let shape = path.is_some() && (primitives::has_fill(node) || primitives::stroke_width(node) > 0.0 || Shadow::of(node).is_some());
+    let has_content = node.tag == "image" || node.text.is_some();
+    if !shape && !has_content { return Ok(()) }
+
+    let (width, height) = (target.pixmap.width() as f32, target.pixmap.height() as f32);
+    let visible = Rect::from_xywh(target.origin.0 as f32, target.origin.1 as f32, width, height);
+    let Some(bounds) = own_bounds(ctx, node, node_box, path)
+        .and_then(|b| b.outset(1.0, 1.0))
+        .and_then(|b| visible.and_then(|v| v.intersect(&b)))
+        .and_then(|b| b.round_out()) else { return Ok(()) };
+    let origin = (bounds.x(), bounds.y());
+
+    let mut layer = new_layer(ctx, bounds.width(), bounds.height())?;
+    let mut layer_surface = Surface::new(layer.as_mut(), origin);
+    if let Some(path) = path {
+        if let Some(shadow) = Shadow::of(node).filter(|s| !s.inset) { draw_shadow(ctx, node, node_box, path, &shadow, &mut layer_surface)?; }
+        draw_primitive(&mut layer_surface, node, path, transform, 1.0, w, h);
+    }
+    if has_content || (path.is_some() && Shadow::of(node).is_some_and(|s| s.inset)) {
+        let mut content_layer = new_layer(ctx, bounds.width(), bounds.height())?;
+        draw_own_content(ctx, node, node_box, path, &mut Surface::new(content_layer.as_mut(), origin))?;
+        layer.draw_pixmap(0, 0, content_layer.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+    }
+    target.pixmap.draw_pixmap(origin.0 - target.origin.0, origin.1 - target.origin.1, layer.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+    Ok(())
+}
+
+/// Draws `node` into `target`, laid out and transformed inside `parent`.
+///
+/// Nodes that need compositing (opacity, blend mode, clip, filter, backdrop
+/// filter, LUT, mask or 3D projection) are rendered into a layer sized to their
+/// painted bounds. Everything else paints itself through `draw_own` and draws
+/// its children straight into `target`.
+pub fn draw_scene<'a>(
+    ctx: &SceneContext, node: &'a SceneNode, layout_id: Node, target: &mut Surface, parent: &NodeBox<'a>
+) -> Result<(), RenderError> {
+    let node_box = node_box(ctx.taffy, node, layout_id, parent)?;
+    let (w, h) = (node_box.w, node_box.h);
+    if node_box.projection.as_ref().is_some_and(|p| p.back_facing) && node.style.backface_visibility.as_deref() == Some("hidden") {
+        return Ok(());
+    }
+
+    let opacity = node.style.opacity.unwrap_or(1.0);
     let blend_mode = parse_blend_mode(node.style.blend_mode.as_deref().unwrap_or("normal"));
     let path = generate_path(node, w, h);
+    let clipped = is_clipped(node);
+    let drop_shadow = Shadow::of(node).filter(|s| !s.inset);
+    let context = parent.canvas || starts_context(node, &node_box);
 
-    let has_radius = node.style.border_radius.unwrap_or(0.0) > 0.0 || node.tag == "circle";
-    let is_clipped = node.style.overflow.as_deref() == Some("hidden") || has_radius;
+    let flat = node_box.projection.is_none();
+    if !is_layered(node, &node_box) {
+        draw_own(ctx, node, &node_box, path.as_ref(), target)?;
+        return draw_children(ctx, node, layout_id, &node_box, target, context);
+    }
 
-    let mut layer = Pixmap::new(pixmap.width(), pixmap.height()).unwrap();
-    layer.fill(Color::TRANSPARENT);
+    // Filters spread the content by up to `reach`, and only content within
+    // `reach` of the target can land on it. What they spread is kept up to
+    // `reach` past the target too, so that a layer edge never chops paths next
+    // to it, but no further than a canvas away, as with projection, which can
+    // pull flat content from beyond the canvas edges into view.
+    let (cw, ch) = (ctx.canvas_width as f32, ctx.canvas_height as f32);
+    let reach = filter_margin(ctx, &filter_ops(&node.style));
+    let Some(content) = unfiltered_bounds(ctx, node, layout_id, &node_box)? else { return Ok(()) };
+    let bounds = if flat {
+        let room = reach.min(cw.max(ch));
+        let (width, height) = (target.pixmap.width() as f32, target.pixmap.height() as f32);
+        let visible = Rect::from_xywh(target.origin.0 as f32, target.origin.1 as f32, width, height);
+        let spread = content.outset(reach, reach).and_then(|c| visible.and_then(|v| v.outset(room, room)).and_then(|v| v.intersect(&c)));
+        let source = visible.and_then(|v| v.outset(reach, reach)).and_then(|v| v.intersect(&content));
+        union(spread, source)
+    } else {
+        let around = Rect::from_xywh(-cw, -ch, cw * 3.0, ch * 3.0);
+        content.outset(reach, reach).and_then(|c| around.and_then(|a| a.intersect(&c)))
+    };
+    let Some(bounds) = bounds.and_then(|b| b.round_out()) else { return Ok(()) };
 
-    draw_primitive(&mut layer, node, &path, transform, 1.0, w, h);
-    
-    let mut content_layer = Pixmap::new(pixmap.width(), pixmap.height()).unwrap();
-    content_layer.fill(Color::TRANSPARENT);
+    let origin = (bounds.x(), bounds.y());
 
-    if node.tag == "image" { draw_image(&mut content_layer, node, &path, transform, engine, w, h, 1.0); }
-    if let Some(t) = &node.text { draw_text(&mut content_layer, node, t, transform, engine, w, 1.0); }
-    if let Ok(child_ids) = taffy.children(layout_id) {
-        if let Some(children) = &node.children {
-            for (child, &cid) in children.iter().zip(child_ids.iter()) {
-                draw_scene(taffy, child, cid, &mut content_layer, engine, x, y, 1.0);
-            }
-        }
+    let mut layer = new_layer(ctx, bounds.width(), bounds.height())?;
+    let mut layer_surface = Surface::new(layer.as_mut(), origin);
+    if let Some(path) = &path {
+        if flat && has_backdrop(node) { draw_backdrop(ctx, node, &node_box, path, target, &mut layer_surface)?; }
+        if let Some(shadow) = &drop_shadow { draw_shadow(ctx, node, &node_box, path, shadow, &mut layer_surface)?; }
+        draw_primitive(&mut layer_surface, node, path, node_box.transform, 1.0, w, h);
     }
+    // Without a shape there is nothing inside the clip.
+    let clip_mask = if clipped { path.as_ref().and_then(|p| layer_surface.path_mask(p, primitives::fill_rule(node), node_box.transform)) } else { None };
+
+    let mut content_layer = new_layer(ctx, bounds.width(), bounds.height())?;
+    draw_content(ctx, node, layout_id, &node_box, path.as_ref(), &mut Surface::new(content_layer.as_mut(), origin), context)?;
 
-    if is_clipped {
-        if let Some(mut clip_mask) = Mask::new(pixmap.width(), pixmap.height()) {
-            clip_mask.fill_path(&path, FillRule::Winding, true, transform);
+    if clipped {
+        if let Some(clip_mask) = clip_mask {
             layer.draw_pixmap(0, 0, content_layer.as_ref(), &PixmapPaint::default(), Transform::identity(), Some(&clip_mask));
         }
     } else {
         layer.draw_pixmap(0, 0, content_layer.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
     }
 
-    if node.style.blur.is_some() { crate::filters::apply_image_filters(&mut layer, &node.style); }
+    let region = FilterRegion { origin, transform: node_box.transform, width: w, height: h };
+    apply_filters(&mut layer, &filter_ops(&node.style), &region, EdgeMode::Transparent, &mut ctx.engine.blur_scratch.borrow_mut());
+    if let Some(name) = &node.style.lut {
+        match ctx.engine.luts.get(name) {
+            Some(lut) => lut.apply(&mut layer, node.style.lut_intensity.unwrap_or(1.0), LutInterpolation::parse(node.style.lut_interpolation.as_deref())),
+            None => ctx.engine.warn(RenderWarning::MissingLut { name: name.clone() }),
+        }
+    }
 
-    let mut lp = PixmapPaint::default();
-    lp.blend_mode = blend_mode; lp.opacity = current_opacity;
-    
-    let mask = if let Some(m) = &node.mask {
-        draw_mask_node(m, pixmap.width(), pixmap.height(), engine, node.style.mask_mode.as_deref().unwrap_or("alpha"))
-    } else { None };
+    let lp = PixmapPaint { blend_mode, opacity, ..PixmapPaint::default() };
+    // The mask is laid out against the canvas, then moves with the node's transform.
+    let mask_base = node_box.transform.pre_translate(-node_box.x, -node_box.y);
+    let mask_mode = node.style.mask_mode.as_deref().unwrap_or("alpha");
 
-    pixmap.draw_pixmap(0, 0, layer.as_ref(), &lp, Transform::identity(), mask.as_ref());
-}
\ No newline at end of file
+    if let Some(projection) = &node_box.projection {
+        // Mask the flat layer so the mask is projected along with it.
+        if let Some(m) = &node.mask {
+            if let Some(mask) = draw_mask_node(ctx, m, &Surface::new(layer.as_mut(), origin), mask_base, mask_mode, node_box.text)? {
+                layer.apply_mask(&mask);
+            }
+        }
+        let reach = IntRect::from_xywh(target.origin.0, target.origin.1, target.pixmap.width(), target.pixmap.height());
+        let Some(area) = project_bounds(projection, bounds.to_rect()).round_out()
+            .and_then(|r| reach.and_then(|t| t.intersect(&r))) else { return Ok(()) };
+        let mut projected = new_layer(ctx, area.width(), area.height())?;
+        transform::warp(&layer, origin, &projection.map, &mut projected, (area.x(), area.y()));
+        target.pixmap.draw_pixmap(area.x() - target.origin.0, area.y() - target.origin.1, projected.as_ref(), &lp, Transform::identity(), None);
+        return Ok(());
+    }
+
+    let mask = match &node.mask {
+        Some(m) => draw_mask_node(ctx, m, target, mask_base, mask_mode, node_box.text)?,
+        None => None,
+    };
+
+    target.pixmap.draw_pixmap(origin.0 - target.origin.0, origin.1 - target.origin.1, layer.as_ref(), &lp, Transform::identity(), mask.as_ref());
+    Ok(())
+}
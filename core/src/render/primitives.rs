@@ -1,87 +1,166 @@
 use tiny_skia::*;
-use crate::types::SceneNode;
-use crate::utils::parse_color;
+use crate::types::{GradientConfig, PaintConfig, SceneNode};
+use crate::utils::{parse_color, trim_path};
+use super::Surface;
 
-pub fn draw_primitive(
-    pixmap: &mut Pixmap, 
-    node: &SceneNode, 
-    path: &Path, 
-    transform: Transform, 
-    opacity: f32,
-    w: f32,
-    h: f32
-) {
-    let mut paint = Paint::default();
-    paint.anti_alias = true;
+/// The node's cap, join and dash settings for a stroke `width` wide.
+pub fn node_stroke(node: &SceneNode, width: f32) -> Stroke {
+    let line_cap = match node.style.stroke_line_cap.as_deref() {
+        Some("round") => LineCap::Round,
+        Some("square") => LineCap::Square,
+        _ => LineCap::Butt,
+    };
+    let line_join = match node.style.stroke_line_join.as_deref() {
+        Some("round") => LineJoin::Round,
+        Some("bevel") => LineJoin::Bevel,
+        _ => LineJoin::Miter,
+    };
+    // Like SVG, an odd-length dash array is repeated to make it even.
+    let dash = node.style.stroke_dash_array.as_ref().and_then(|array| {
+        let array = if array.len() % 2 == 1 { array.repeat(2) } else { array.clone() };
+        StrokeDash::new(array, node.style.stroke_dash_offset.unwrap_or(0.0))
+    });
+    Stroke { width, line_cap, line_join, dash, ..Default::default() }
+}
 
-    let mut has_fill = false;
+/// What is left of `path` to stroke after the node's `trim*` percentages.
+pub fn trimmed_path(node: &SceneNode, path: &Path) -> Option<Path> {
+    let s = &node.style;
+    if s.trim_start.is_none() && s.trim_end.is_none() && s.trim_offset.is_none() {
+        return Some(path.clone());
+    }
+    let start = s.trim_start.unwrap_or(0.0) / 100.0;
+    let end = s.trim_end.unwrap_or(100.0) / 100.0;
+    trim_path(path, start, end, s.trim_offset.unwrap_or(0.0) / 100.0)
+}
 
-    if let Some(grad) = &node.style.background_gradient {
-        let mut stops = Vec::new();
-        for i in 0..grad.colors.len() {
-            let mut color = parse_color(&grad.colors[i]);
-            color.set_alpha(color.alpha() * opacity);
-            let pos = match &grad.stops {
-                Some(s) => s[i],
-                None => i as f32 / (grad.colors.len() as f32 - 1.0).max(1.0),
-            };
-            stops.push(GradientStop::new(pos, color));
+/// Where a fill or stroke gets its color from.
+enum PaintSource<'a> {
+    Color(&'a str),
+    Gradient(&'a GradientConfig),
+}
+
+impl<'a> PaintSource<'a> {
+    fn of(config: &'a PaintConfig) -> Self {
+        match config {
+            PaintConfig::Color(c) => PaintSource::Color(c),
+            PaintConfig::Gradient(g) => PaintSource::Gradient(g),
         }
+    }
+}
 
-        if grad.r#type.as_deref() == Some("radial") {
-            let center = Point::from_xy(w / 2.0, h / 2.0);
-            if let Some(shader) = RadialGradient::new(center, center, (w.max(h)) / 1.2, stops, SpreadMode::Pad, Transform::identity()) {
-                paint.shader = shader; has_fill = true;
-            }
+/// `fill` wins over the box-style background; a gradient background wins over a color.
+fn fill_source(node: &SceneNode) -> Option<PaintSource<'_>> {
+    match &node.fill {
+        Some(fill) => Some(PaintSource::of(fill)),
+        None => node.style.background_gradient.as_ref().map(PaintSource::Gradient)
+            .or(node.style.background_color.as_deref().map(PaintSource::Color)),
+    }
+}
+
+fn stroke_source(node: &SceneNode) -> Option<PaintSource<'_>> {
+    match &node.stroke {
+        Some(stroke) => Some(PaintSource::of(stroke)),
+        None => node.style.border_color.as_deref().map(PaintSource::Color),
+    }
+}
+
+pub fn has_fill(node: &SceneNode) -> bool {
+    fill_source(node).is_some()
+}
+
+/// Width of the node's stroke, or 0 when it has none. `stroke_width` wins over
+/// `border_width`; a `stroke` without either is 1px wide, like SVG.
+pub fn stroke_width(node: &SceneNode) -> f32 {
+    if stroke_source(node).is_none() { return 0.0; }
+    let default = if node.stroke.is_some() { 1.0 } else { 0.0 };
+    node.stroke_width.or(node.style.border_width).unwrap_or(default).max(0.0)
+}
+
+pub fn fill_rule(node: &SceneNode) -> FillRule {
+    match node.fill_rule.as_deref() {
+        Some("evenodd") => FillRule::EvenOdd,
+        _ => FillRule::Winding,
+    }
+}
+
+fn gradient_shader(grad: &GradientConfig, opacity: f32, w: f32, h: f32) -> Option<Shader<'static>> {
+    let mut stops = Vec::new();
+    for i in 0..grad.colors.len() {
+        let mut color = parse_color(&grad.colors[i]);
+        color.set_alpha(color.alpha() * opacity);
+        // Colors past the end of `stops` fall back to even spacing.
+        let even = i as f32 / (grad.colors.len() as f32 - 1.0).max(1.0);
+        let pos = grad.stops.as_ref().and_then(|s| s.get(i).copied()).unwrap_or(even);
+        stops.push(GradientStop::new(pos, color));
+    }
+
+    if grad.r#type.as_deref() == Some("radial") {
+        let center = Point::from_xy(w / 2.0, h / 2.0);
+        RadialGradient::new(center, center, (w.max(h)) / 1.2, stops, SpreadMode::Pad, Transform::identity())
+    } else {
+        let angle = grad.angle.unwrap_or(180.0) % 360.0;
+
+        // PIXEL-PERFECT AXIAL MAPPING: 
+        // We map to pixel centers (0.5 to length-0.5) to ensure 
+        // the gradient reaches full value within the pixel grid.
+        let (start, end) = if angle == 90.0 {
+            (Point::from_xy(0.5, h / 2.0), Point::from_xy(w - 0.5, h / 2.0))
+        } else if angle == 180.0 {
+            (Point::from_xy(w / 2.0, 0.5), Point::from_xy(w / 2.0, h - 0.5))
+        } else if angle == 270.0 {
+            (Point::from_xy(w - 0.5, h / 2.0), Point::from_xy(0.5, h / 2.0))
+        } else if angle == 0.0 || angle == 360.0 {
+            (Point::from_xy(w / 2.0, h - 0.5), Point::from_xy(w / 2.0, 0.5))
         } else {
-            let angle = grad.angle.unwrap_or(180.0) % 360.0;
-            
-            // PIXEL-PERFECT AXIAL MAPPING: 
-            // We map to pixel centers (0.5 to length-0.5) to ensure 
-            // the gradient reaches full value within the pixel grid.
-            let (start, end) = if angle == 90.0 {
-                (Point::from_xy(0.5, h / 2.0), Point::from_xy(w - 0.5, h / 2.0))
-            } else if angle == 180.0 {
-                (Point::from_xy(w / 2.0, 0.5), Point::from_xy(w / 2.0, h - 0.5))
-            } else if angle == 270.0 {
-                (Point::from_xy(w - 0.5, h / 2.0), Point::from_xy(0.5, h / 2.0))
-            } else if angle == 0.0 || angle == 360.0 {
-                (Point::from_xy(w / 2.0, h - 0.5), Point::from_xy(w / 2.0, 0.5))
-            } else {
-                let rad = (angle - 90.0).to_radians();
-                let (sin, cos) = rad.sin_cos();
-                let len = w.abs() * cos.abs() + h.abs() * sin.abs();
-                let half_l = len / 2.0;
-                let (cx, cy) = (w / 2.0, h / 2.0);
-                (Point::from_xy(cx - cos * half_l, cy - sin * half_l),
-                 Point::from_xy(cx + cos * half_l, cy + sin * half_l))
-            };
-
-            if let Some(shader) = LinearGradient::new(start, end, stops, SpreadMode::Pad, Transform::identity()) {
-                paint.shader = shader; has_fill = true;
-            }
+            let rad = (angle - 90.0).to_radians();
+            let (sin, cos) = rad.sin_cos();
+            let len = w.abs() * cos.abs() + h.abs() * sin.abs();
+            let half_l = len / 2.0;
+            let (cx, cy) = (w / 2.0, h / 2.0);
+            (Point::from_xy(cx - cos * half_l, cy - sin * half_l),
+             Point::from_xy(cx + cos * half_l, cy + sin * half_l))
+        };
+
+        LinearGradient::new(start, end, stops, SpreadMode::Pad, Transform::identity())
+    }
+}
+
+fn paint_from(source: PaintSource, opacity: f32, w: f32, h: f32) -> Option<Paint<'static>> {
+    let mut paint = Paint { anti_alias: true, ..Default::default() };
+    match source {
+        PaintSource::Gradient(grad) => paint.shader = gradient_shader(grad, opacity, w, h)?,
+        PaintSource::Color(c) => {
+            let mut color = parse_color(c);
+            color.set_alpha(color.alpha() * opacity);
+            paint.set_color(color);
         }
-    } else if let Some(bg) = &node.style.background_color {
-        let mut color = parse_color(bg);
-        color.set_alpha(color.alpha() * opacity);
-        paint.set_color(color);
-        has_fill = true;
     }
+    Some(paint)
+}
 
+pub fn draw_primitive(
+    pixmap: &mut Surface, 
+    node: &SceneNode, 
+    path: &Path, 
+    transform: Transform, 
+    opacity: f32,
+    w: f32,
+    h: f32
+) {
     // CRITICAL: Only fill if a color or gradient was actually provided
-    if has_fill {
-        pixmap.fill_path(path, &paint, FillRule::Winding, transform, None);
+    let fill_opacity = opacity * node.fill_opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+    if let Some(paint) = fill_source(node).and_then(|source| paint_from(source, fill_opacity, w, h)) {
+        pixmap.fill_path(path, &paint, fill_rule(node), transform, None);
     }
 
-    if let Some(bw) = node.style.border_width {
-        if bw > 0.0 {
-            if let Some(bc) = &node.style.border_color {
-                let mut sp = Paint::default();
-                let mut color = parse_color(bc);
-                color.set_alpha(color.alpha() * opacity);
-                sp.set_color(color);
-                pixmap.stroke_path(path, &sp, &Stroke { width: bw, ..Default::default() }, transform, None);
+    let width = stroke_width(node);
+    if width > 0.0 {
+        let stroke_opacity = opacity * node.stroke_opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+        if let Some(paint) = stroke_source(node).and_then(|source| paint_from(source, stroke_opacity, w, h)) {
+            if let Some(path) = trimmed_path(node, path) {
+                pixmap.stroke_path(&path, &paint, &node_stroke(node, width), transform, None);
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,206 @@
+use tiny_skia::*;
+use crate::error::RenderError;
+use crate::filters::{gaussian_blur, EdgeMode};
+use crate::types::SceneNode;
+use crate::utils::parse_color;
+use super::primitives::{fill_rule, has_fill, node_stroke, stroke_width, trimmed_path};
+use super::{blur_margin, draw_image, draw_primitive, draw_text, local_bounds, new_layer, union, NodeBox, SceneContext, Surface};
+
+/// A node's `shadow*` style. Offsets and spread are in node units, the blur
+/// radius in canvas pixels like CSS `box-shadow`.
+pub struct Shadow {
+    color: Color,
+    sigma: f32,
+    offset_x: f32,
+    offset_y: f32,
+    spread: f32,
+    pub inset: bool,
+}
+
+impl Shadow {
+    pub fn of(node: &SceneNode) -> Option<Shadow> {
+        let s = &node.style;
+        let set = s.shadow_color.is_some() || s.shadow_blur.is_some() || s.shadow_spread.is_some()
+            || s.shadow_offset_x.is_some() || s.shadow_offset_y.is_some();
+        if !set { return None; }
+
+        let color = parse_color(s.shadow_color.as_deref().unwrap_or("#00000080"));
+        if color.alpha() == 0.0 { return None; }
+        Some(Shadow {
+            color,
+            sigma: s.shadow_blur.unwrap_or(0.0).max(0.0) / 2.0,
+            offset_x: s.shadow_offset_x.unwrap_or(0.0),
+            offset_y: s.shadow_offset_y.unwrap_or(0.0),
+            spread: s.shadow_spread.unwrap_or(0.0),
+            inset: s.shadow_inset.unwrap_or(false),
+        })
+    }
+
+    /// Canvas area an outer shadow can reach, given the node-local bounds of what casts it.
+    pub fn bounds(&self, local: Rect, transform: Transform) -> Option<Rect> {
+        let margin = blur_margin(self.sigma) + 1.0;
+        self.cast_bounds(local, transform)?.outset(margin, margin)
+    }
+
+    /// Like `bounds`, but before the blur spreads it.
+    fn cast_bounds(&self, local: Rect, transform: Transform) -> Option<Rect> {
+        if self.inset { return None; }
+        let spread = self.spread.max(0.0);
+        local.outset(spread, spread)?.transform(transform.pre_translate(self.offset_x, self.offset_y))
+    }
+}
+
+/// Text and images cast their drawn pixels, like CSS `drop-shadow()`. Every
+/// other node casts its shape, like `box-shadow`.
+fn casts_pixels(node: &SceneNode) -> bool {
+    node.tag == "image" || node.text.is_some()
+}
+
+/// Fills the node's shape grown by `spread` (shrunk when negative) with `blend_mode`.
+fn draw_shape(target: &mut Surface, node: &SceneNode, path: &Path, transform: Transform, spread: f32, blend_mode: BlendMode) {
+    let paint = Paint { anti_alias: true, blend_mode, ..Default::default() };
+    let erase = Paint { blend_mode: if blend_mode == BlendMode::SourceOver { BlendMode::DestinationOut } else { BlendMode::SourceOver }, ..paint.clone() };
+    // Growing a shape by `s` is stroking its outline `2s` wide on top of the fill,
+    // shrinking it is erasing that stroke. A stroke already adds half its width.
+    let outline = stroke_width(node) + spread * 2.0;
+    let stroke = |width: f32| Stroke {
+        width,
+        line_join: if spread == 0.0 { LineJoin::Miter } else { LineJoin::Round },
+        ..Default::default()
+    };
+
+    // Open paths without a fill only cast their stroke.
+    let filled = node.tag != "path" || has_fill(node);
+    if filled {
+        target.fill_path(path, &paint, fill_rule(node), transform, None);
+        if outline > 0.0 {
+            target.stroke_path(path, &paint, &stroke(outline), transform, None);
+        } else if outline < 0.0 {
+            target.stroke_path(path, &erase, &stroke(-outline), transform, None);
+        }
+    } else if outline > 0.0 {
+        if let Some(path) = trimmed_path(node, path) {
+            target.stroke_path(&path, &paint, &node_stroke(node, outline), transform, None);
+        }
+    }
+}
+
+fn draw_pixels(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: &Path, transform: Transform, target: &mut Surface) {
+    let (w, h) = (node_box.w, node_box.h);
+    draw_primitive(target, node, path, transform, 1.0, w, h);
+    if node.tag == "image" { draw_image(target, node, transform, ctx.engine, w, h, 1.0); }
+    if let Some(t) = &node.text { draw_text(target, node, &node_box.text, t, transform, ctx.engine, w, 1.0); }
+}
+
+/// Grows (`radius > 0`) or shrinks the alpha channel by a square of `radius` pixels.
+fn spread_alpha(pixmap: &mut Pixmap, radius: i32) {
+    if radius == 0 { return; }
+    let (w, h) = (pixmap.width() as usize, pixmap.height() as usize);
+    let r = radius.unsigned_abs() as usize;
+    let pick = |a: u8, b: u8| if radius > 0 { a.max(b) } else { a.min(b) };
+
+    let mut alpha: Vec<u8> = pixmap.data().chunks_exact(4).map(|p| p[3]).collect();
+    let mut line = Vec::new();
+    for (len, stride, count, step) in [(w, 1, h, w), (h, w, w, 1)] {
+        for i in 0..count {
+            let start = i * step;
+            line.clear();
+            line.extend((0..len).map(|j| alpha[start + j * stride]));
+            for j in 0..len {
+                let window = &line[j.saturating_sub(r)..(j + r + 1).min(len)];
+                alpha[start + j * stride] = window.iter().fold(line[j], |v, &a| pick(v, a));
+            }
+        }
+    }
+
+    for (p, a) in pixmap.data_mut().chunks_exact_mut(4).zip(alpha) { p[3] = a; }
+}
+
+/// Replaces every pixel with `color` at that pixel's coverage.
+fn colorize(pixmap: &mut Pixmap, color: Color) {
+    let c = color.premultiply().to_color_u8();
+    let scale = |v: u8, a: u8| ((v as u16 * a as u16 + 127) / 255) as u8;
+    for p in pixmap.data_mut().chunks_exact_mut(4) {
+        let a = p[3];
+        p[0] = scale(c.red(), a);
+        p[1] = scale(c.green(), a);
+        p[2] = scale(c.blue(), a);
+        p[3] = scale(c.alpha(), a);
+    }
+}
+
+/// Draws the node's shadow into `target`. Outer shadows belong under the node,
+/// inset shadows over its fill and below its children.
+pub fn draw_shadow(ctx: &SceneContext, node: &SceneNode, node_box: &NodeBox, path: &Path, shadow: &Shadow, target: &mut Surface) -> Result<(), RenderError> {
+    let transform = node_box.transform;
+    let Some(local) = local_bounds(ctx, node, node_box, Some(path)) else { return Ok(()) };
+
+    // The blur reads around what ends up visible, so the work area extends past
+    // the target by the blur margin. Wide blurs stop a canvas away: an inset
+    // shadow's layer is opaque past the shape and the blur repeats its edges,
+    // and an outer shadow only needs the full margin where it is cast.
+    let margin = blur_margin(shadow.sigma) + 1.0;
+    let room = margin.min(ctx.canvas_width.max(ctx.canvas_height) as f32);
+    let visible = IntRect::from_xywh(target.origin.0, target.origin.1, target.pixmap.width(), target.pixmap.height()).map(|r| r.to_rect());
+    let around = visible.and_then(|v| v.outset(room, room));
+    let area = if shadow.inset {
+        local.transform(transform).and_then(|r| r.outset(room, room)).and_then(|r| around.and_then(|v| v.intersect(&r)))
+    } else {
+        let cast = shadow.cast_bounds(local, transform);
+        let spread = cast.and_then(|c| c.outset(margin, margin)).and_then(|c| around.and_then(|v| v.intersect(&c)));
+        let source = visible.and_then(|v| v.outset(margin, margin)).and_then(|v| cast.and_then(|c| v.intersect(&c)));
+        union(spread, source)
+    };
+    let Some(area) = area.and_then(|r| r.round_out()) else { return Ok(()) };
+
+    let origin = (area.x(), area.y());
+    let mut layer = new_layer(ctx, area.width(), area.height())?;
+    let offset = transform.pre_translate(shadow.offset_x, shadow.offset_y);
+    // An inset shadow is cast by everything outside the shape, so spreading it shrinks the hole.
+    let spread = if shadow.inset { -shadow.spread } else { shadow.spread };
+
+    if casts_pixels(node) {
+        draw_pixels(ctx, node, node_box, path, offset, &mut Surface::new(layer.as_mut(), origin));
+        let scale = (transform.sx * transform.sy - transform.kx * transform.ky).abs().sqrt();
+        spread_alpha(&mut layer, (spread * scale).round() as i32);
+    } else {
+        draw_shape(&mut Surface::new(layer.as_mut(), origin), node, path, offset, spread, BlendMode::SourceOver);
+    }
+
+    if shadow.inset {
+        for p in layer.data_mut().chunks_exact_mut(4) { p[3] = 255 - p[3]; }
+    }
+    if shadow.sigma > 0.0 {
+        // An inset shadow's layer is opaque beyond its edges, not empty.
+        let edge = if shadow.inset { EdgeMode::Clamp } else { EdgeMode::Transparent };
+        gaussian_blur(&mut layer, shadow.sigma, edge, &mut ctx.engine.blur_scratch.borrow_mut());
+    }
+    colorize(&mut layer, shadow.color);
+
+    let mut surface = Surface::new(layer.as_mut(), origin);
+    match (shadow.inset, casts_pixels(node)) {
+        // Inset shadows only show inside what casts them.
+        (true, true) => {
+            let mut source = new_layer(ctx, area.width(), area.height())?;
+            draw_pixels(ctx, node, node_box, path, transform, &mut Surface::new(source.as_mut(), origin));
+            layer.apply_mask(&Mask::from_pixmap(source.as_ref(), MaskType::Alpha));
+        }
+        (true, false) => {
+            // Keep the border on top, as CSS paints inset shadows inside the border box.
+            if let Some(mask) = surface.path_mask(path, fill_rule(node), transform) {
+                let border = stroke_width(node);
+                if let Some(outline) = trimmed_path(node, path).filter(|_| border > 0.0) {
+                    let erase = Paint { anti_alias: true, blend_mode: BlendMode::DestinationOut, ..Default::default() };
+                    surface.stroke_path(&outline, &erase, &node_stroke(node, border), transform, None);
+                }
+                layer.apply_mask(&mask);
+            }
+        }
+        // Like CSS, a box shadow never shows through the box that casts it.
+        (false, false) => draw_shape(&mut surface, node, path, transform, 0.0, BlendMode::DestinationOut),
+        (false, true) => {}
+    }
+
+    target.pixmap.draw_pixmap(origin.0 - target.origin.0, origin.1 - target.origin.1, layer.as_ref(), &PixmapPaint::default(), Transform::identity(), None);
+    Ok(())
+}
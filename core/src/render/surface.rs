@@ -0,0 +1,103 @@
+use tiny_skia::*;
+
+/// A pixmap covering part of the canvas. `origin` is the canvas position of its top-left pixel.
+///
+/// Drawing calls take canvas-space transforms. Geometry is mapped into canvas
+/// space first and shifted by the integer origin afterwards, so a node lands on
+/// exactly the same pixel grid (and anti-aliasing) as it would on a full canvas.
+pub struct Surface<'a> {
+    pub pixmap: PixmapMut<'a>,
+    pub origin: (i32, i32),
+}
+
+impl<'a> Surface<'a> {
+    pub fn new(pixmap: PixmapMut<'a>, origin: (i32, i32)) -> Self {
+        Surface { pixmap, origin }
+    }
+
+    fn shift(&self) -> Transform {
+        Transform::from_translate(-self.origin.0 as f32, -self.origin.1 as f32)
+    }
+
+    /// Maps a canvas-space transform onto this surface's pixels.
+    pub fn local(&self, transform: Transform) -> Transform {
+        transform.post_concat(self.shift())
+    }
+
+    pub fn fill_path(&mut self, path: &Path, paint: &Paint, fill_rule: FillRule, transform: Transform, mask: Option<&Mask>) {
+        if self.origin == (0, 0) {
+            self.pixmap.fill_path(path, paint, fill_rule, transform, mask);
+            return;
+        }
+        let Some(path) = path.clone().transform(transform).and_then(|p| p.transform(self.shift())) else { return };
+        let mut paint = paint.clone();
+        paint.shader.transform(transform);
+        paint.shader.transform(self.shift());
+        self.pixmap.fill_path(&path, &paint, fill_rule, Transform::identity(), mask);
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, paint: &Paint, stroke: &Stroke, transform: Transform, mask: Option<&Mask>) {
+        if self.origin == (0, 0) {
+            self.pixmap.stroke_path(path, paint, stroke, transform, mask);
+            return;
+        }
+        if is_hairline(paint, stroke, transform) {
+            // Hairlines are rasterized directly rather than filled; there is no
+            // canvas-space outline to shift, so the transform carries the offset.
+            self.pixmap.stroke_path(path, paint, stroke, self.local(transform), mask);
+            return;
+        }
+        // Mirrors `PixmapMut::stroke_path`: dash and stroke in local space, then fill.
+        let res_scale = PathStroker::compute_resolution_scale(&transform);
+        let dashed;
+        let path = match &stroke.dash {
+            Some(dash) => match path.dash(dash, res_scale) {
+                Some(p) => { dashed = p; &dashed },
+                None => return,
+            },
+            None => path,
+        };
+        if let Some(outline) = path.stroke(stroke, res_scale) {
+            self.fill_path(&outline, paint, FillRule::Winding, transform, mask);
+        }
+    }
+
+    /// Mirrors `PixmapMut::draw_pixmap`, routed through `fill_path` so the edges stay on the canvas grid.
+    pub fn draw_pixmap(&mut self, pixmap: PixmapRef, paint: &PixmapPaint, transform: Transform, mask: Option<&Mask>) {
+        if self.origin == (0, 0) {
+            self.pixmap.draw_pixmap(0, 0, pixmap, paint, transform, mask);
+            return;
+        }
+        let Some(rect) = Rect::from_xywh(0.0, 0.0, pixmap.width() as f32, pixmap.height() as f32) else { return };
+        let paint = Paint {
+            shader: Pattern::new(pixmap, SpreadMode::Pad, paint.quality, paint.opacity, Transform::identity()),
+            blend_mode: paint.blend_mode,
+            anti_alias: false,
+            force_hq_pipeline: false,
+        };
+        self.fill_path(&PathBuilder::from_rect(rect), &paint, FillRule::Winding, transform, mask);
+    }
+
+    /// Coverage mask of `path` with this surface's size and position.
+    pub fn path_mask(&self, path: &Path, fill_rule: FillRule, transform: Transform) -> Option<Mask> {
+        let mut mask = Mask::new(self.pixmap.width(), self.pixmap.height())?;
+        let path = path.clone().transform(transform)?.transform(self.shift())?;
+        mask.fill_path(&path, fill_rule, true, Transform::identity());
+        Some(mask)
+    }
+}
+
+/// Same test tiny-skia uses to switch to hairline stroking.
+fn is_hairline(paint: &Paint, stroke: &Stroke, mut ts: Transform) -> bool {
+    if stroke.width == 0.0 { return true; }
+    if !paint.anti_alias { return false; }
+    ts.tx = 0.0;
+    ts.ty = 0.0;
+    let mut points = [Point::from_xy(stroke.width, 0.0), Point::from_xy(0.0, stroke.width)];
+    ts.map_points(&mut points);
+    let fast_len = |p: Point| {
+        let (x, y) = (p.x.abs(), p.y.abs());
+        x.max(y) + x.min(y) / 2.0
+    };
+    fast_len(points[0]) <= 1.0 && fast_len(points[1]) <= 1.0
+}
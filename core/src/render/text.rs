@@ -2,11 +2,15 @@ use tiny_skia::*;
 use crate::types::SceneNode;
 use crate::utils::{parse_color, parse_blend_mode};
 use crate::engine::EngineCore;
-use crate::text::compute_text_lines;
+use crate::error::RenderWarning;
+use crate::text::{compute_text_lines, TextStyle};
+use super::Surface;
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_text(
-    pixmap: &mut Pixmap,
+    pixmap: &mut Surface,
     node: &SceneNode,
+    text: &TextStyle,
     text_content: &str,
     transform: Transform,
     engine: &EngineCore,
@@ -14,26 +18,24 @@ pub fn draw_text(
     opacity: f32
 ) {
     let blend_mode = parse_blend_mode(node.style.blend_mode.as_deref().unwrap_or("normal"));
-    let font_name = node.style.font_family.as_deref().unwrap_or("default");
+    let font_name = text.font_family.unwrap_or("default");
     let font_opt = engine.fonts.get(font_name).or_else(|| engine.fonts.values().next());
+    if font_opt.is_none() || (text.font_family.is_some() && !engine.fonts.contains_key(font_name)) {
+        engine.warn(RenderWarning::MissingFont { family: font_name.to_string() });
+    }
     
     if let Some(font) = font_opt {
-        let size = node.style.font_size.unwrap_or(32.0);
-        let color = parse_color(node.style.color.as_deref().unwrap_or("#ffffff"));
-        let lh = node.style.line_height.unwrap_or(size * 1.2);
-        let letter_spacing = node.style.letter_spacing.unwrap_or(0.0);
-        let align = node.style.text_align.as_deref().unwrap_or("left");
+        let size = text.font_size();
+        let color = parse_color(text.color.unwrap_or("#ffffff"));
+        let lh = text.line_height();
+        let letter_spacing = text.letter_spacing();
 
         let wrap_width = if w > 0.0 { Some(w) } else { None };
         let lines = compute_text_lines(font, text_content, size, letter_spacing, wrap_width);
 
         for (li, line) in lines.iter().enumerate() {
             let ly = li as f32 * lh;
-            let mut cx = match align {
-                "center" => if w > 0.0 { (w - line.width) / 2.0 } else { 0.0 },
-                "right" => if w > 0.0 { w - line.width } else { 0.0 },
-                _ => 0.0
-            };
+            let mut cx = line_offset(text.text_align, w, line.width);
 
             for (c, adv) in &line.chars {
                 let glyph = engine.get_glyph(font_name, font, *c, size);
@@ -57,10 +59,10 @@ pub fn draw_text(
                     }
 
                     if let Some(glyph_pixmap) = tiny_skia::PixmapRef::from_bytes(dest_slice, gw, gh) {
-                        let cy = (ly + size - metrics.height as f32 - metrics.ymin as f32) as f32;
-                        let mut text_paint = PixmapPaint::default();
-                        text_paint.blend_mode = blend_mode;
-                        pixmap.draw_pixmap(0, 0, glyph_pixmap, &text_paint, transform.post_translate(cx+metrics.xmin as f32, cy), None);
+                        let cy = ly + size - metrics.height as f32 - metrics.ymin as f32;
+                        let text_paint = PixmapPaint { blend_mode, ..PixmapPaint::default() };
+                        // Glyph offsets are node-local, so they go through the node's transform too.
+                        pixmap.draw_pixmap(glyph_pixmap, &text_paint, transform.pre_translate(cx + metrics.xmin as f32, cy), None);
                     }
                 }
                 cx += adv;
@@ -68,3 +70,28 @@ pub fn draw_text(
         }
     }
 }
+
+/// Where a line of `line_width` starts in a text box `w` wide.
+pub fn line_offset(align: Option<&str>, w: f32, line_width: f32) -> f32 {
+    match align.unwrap_or("left") {
+        "center" if w > 0.0 => (w - line_width) / 2.0,
+        "right" if w > 0.0 => w - line_width,
+        _ => 0.0
+    }
+}
+
+/// Node-local area the glyphs of `text_content` can cover. Glyphs may overhang
+/// their advance box, so each side gets a font-size worth of slack.
+pub fn text_bounds(text: &TextStyle, text_content: &str, engine: &EngineCore, w: f32, h: f32) -> Option<Rect> {
+    let font_name = text.font_family.unwrap_or("default");
+    let font = engine.fonts.get(font_name).or_else(|| engine.fonts.values().next())?;
+    let size = text.font_size();
+    let lh = text.line_height();
+    let letter_spacing = text.letter_spacing();
+
+    let wrap_width = if w > 0.0 { Some(w) } else { None };
+    let lines = compute_text_lines(font, text_content, size, letter_spacing, wrap_width);
+    let width = lines.iter().map(|l| l.width).fold(w, f32::max);
+    let height = (lines.len() as f32 * lh).max(h);
+    Rect::from_ltrb(-size, -size, width + size, height + size)
+}
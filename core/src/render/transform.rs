@@ -0,0 +1,259 @@
+use tiny_skia::{Pixmap, Transform};
+use crate::types::StyleConfig;
+
+enum OriginToken {
+    Center,
+    Vertical(f32),
+    Horizontal(f32),
+    Percent(f32),
+    Px(f32),
+}
+
+fn origin_token(token: &str) -> Option<OriginToken> {
+    Some(match token {
+        "left" => OriginToken::Horizontal(0.0),
+        "right" => OriginToken::Horizontal(1.0),
+        "top" => OriginToken::Vertical(0.0),
+        "bottom" => OriginToken::Vertical(1.0),
+        "center" => OriginToken::Center,
+        _ => match token.strip_suffix('%') {
+            Some(p) => OriginToken::Percent(p.parse().ok()?),
+            None => OriginToken::Px(token.strip_suffix("px").unwrap_or(token).parse().ok()?),
+        },
+    })
+}
+
+fn resolve(token: &OriginToken, size: f32) -> f32 {
+    match token {
+        OriginToken::Center => size / 2.0,
+        OriginToken::Vertical(f) | OriginToken::Horizontal(f) => f * size,
+        OriginToken::Percent(p) => p / 100.0 * size,
+        OriginToken::Px(v) => *v,
+    }
+}
+
+/// Resolves `transform_origin` against a `w`x`h` box, like CSS: one or two of
+/// `left`/`center`/`right`/`top`/`bottom`, percentages or pixels. Defaults to the center.
+pub fn transform_origin(style: &StyleConfig, w: f32, h: f32) -> (f32, f32) {
+    let center = (w / 2.0, h / 2.0);
+    let Some(origin) = style.transform_origin.as_deref() else { return center };
+    let Some(tokens) = origin.split_whitespace().map(origin_token).collect::<Option<Vec<_>>>() else { return center };
+
+    match tokens.as_slice() {
+        [OriginToken::Vertical(_)] => (center.0, resolve(&tokens[0], h)),
+        [single] => (resolve(single, w), center.1),
+        // Keywords say which axis they belong to, so "top left" works as well as "left top".
+        [a @ OriginToken::Vertical(_), b] | [a, b @ OriginToken::Horizontal(_)] => (resolve(b, w), resolve(a, h)),
+        [a, b] => (resolve(a, w), resolve(b, h)),
+        _ => center,
+    }
+}
+
+/// The node's own transform, in node-local coordinates: translate, rotate, skew,
+/// scale and then `matrix`, all around the transform origin.
+pub fn local_transform(style: &StyleConfig, w: f32, h: f32) -> Transform {
+    let (ox, oy) = transform_origin(style, w, h);
+    let mut ts = Transform::from_translate(ox, oy);
+
+    let (tx, ty) = (style.translate_x.unwrap_or(0.0), style.translate_y.unwrap_or(0.0));
+    if tx != 0.0 || ty != 0.0 { ts = ts.pre_translate(tx, ty); }
+    if let Some(r) = style.rotate { ts = ts.pre_rotate(r); }
+
+    let (kx, ky) = (style.skew_x.unwrap_or(0.0), style.skew_y.unwrap_or(0.0));
+    if kx != 0.0 || ky != 0.0 {
+        ts = ts.pre_concat(Transform::from_skew(kx.to_radians().tan(), ky.to_radians().tan()));
+    }
+
+    let s = style.scale.unwrap_or(1.0);
+    if style.scale.is_some() || style.scale_x.is_some() || style.scale_y.is_some() {
+        ts = ts.pre_scale(s * style.scale_x.unwrap_or(1.0), s * style.scale_y.unwrap_or(1.0));
+    }
+
+    if let Some([a, b, c, d, e, f]) = style.matrix {
+        ts = ts.pre_concat(Transform::from_row(a, b, c, d, e, f));
+    }
+    ts.pre_translate(-ox, -oy)
+}
+
+/// A projective transform of the plane, row-major, acting on `(x, y, 1)`.
+#[derive(Clone, Copy, Debug)]
+pub struct Homography([f32; 9]);
+
+type Mat4 = [[f32; 4]; 4];
+
+fn mul4(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [[0.0; 4]; 4];
+    for (r, row) in out.iter_mut().enumerate() {
+        for (c, v) in row.iter_mut().enumerate() {
+            *v = (0..4).map(|k| a[r][k] * b[k][c]).sum();
+        }
+    }
+    out
+}
+
+fn affine4(ts: Transform) -> Mat4 {
+    [
+        [ts.sx, ts.kx, 0.0, ts.tx],
+        [ts.ky, ts.sy, 0.0, ts.ty],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]
+}
+
+/// CSS `perspective(d)` around `(cx, cy)`: points toward the viewer (positive z) grow.
+fn perspective4(d: f32, cx: f32, cy: f32) -> Mat4 {
+    let mut p = affine4(Transform::identity());
+    p[3][2] = -1.0 / d;
+    mul4(&mul4(&affine4(Transform::from_translate(cx, cy)), &p), &affine4(Transform::from_translate(-cx, -cy)))
+}
+
+/// CSS `rotateX(x) rotateY(y)`, in degrees.
+fn rotate4(x: f32, y: f32) -> Mat4 {
+    let (sa, ca) = x.to_radians().sin_cos();
+    let (sb, cb) = y.to_radians().sin_cos();
+    let rx = [[1.0, 0.0, 0.0, 0.0], [0.0, ca, -sa, 0.0], [0.0, sa, ca, 0.0], [0.0, 0.0, 0.0, 1.0]];
+    let ry = [[cb, 0.0, sb, 0.0], [0.0, 1.0, 0.0, 0.0], [-sb, 0.0, cb, 0.0], [0.0, 0.0, 0.0, 1.0]];
+    mul4(&rx, &ry)
+}
+
+impl Homography {
+    pub fn from_transform(ts: Transform) -> Self {
+        Homography([ts.sx, ts.kx, ts.tx, ts.ky, ts.sy, ts.ty, 0.0, 0.0, 1.0])
+    }
+
+    /// Restricts a 3D transform to the z = 0 plane, keeping the projective row.
+    fn from_plane(m: &Mat4) -> Self {
+        let pick = [0, 1, 3];
+        let mut out = [0.0; 9];
+        for (r, &mr) in pick.iter().enumerate() {
+            for (c, &mc) in pick.iter().enumerate() {
+                out[r * 3 + c] = m[mr][mc];
+            }
+        }
+        Homography(out)
+    }
+
+    /// `self · other`: applies `other` first.
+    pub fn concat(&self, other: &Homography) -> Homography {
+        let (a, b) = (&self.0, &other.0);
+        let mut out = [0.0; 9];
+        for r in 0..3 {
+            for c in 0..3 {
+                out[r * 3 + c] = (0..3).map(|k| a[r * 3 + k] * b[k * 3 + c]).sum();
+            }
+        }
+        Homography(out)
+    }
+
+    pub fn invert(&self) -> Option<Homography> {
+        let m = &self.0;
+        let cof = [
+            m[4] * m[8] - m[5] * m[7], m[2] * m[7] - m[1] * m[8], m[1] * m[5] - m[2] * m[4],
+            m[5] * m[6] - m[3] * m[8], m[0] * m[8] - m[2] * m[6], m[2] * m[3] - m[0] * m[5],
+            m[3] * m[7] - m[4] * m[6], m[1] * m[6] - m[0] * m[7], m[0] * m[4] - m[1] * m[3],
+        ];
+        let det = m[0] * cof[0] + m[1] * cof[3] + m[2] * cof[6];
+        if det.abs() < f32::EPSILON { return None; }
+        Some(Homography(cof.map(|v| v / det)))
+    }
+
+    /// The homogeneous weight of `(x, y)`; points at or behind the viewer have none left.
+    fn weight(&self, x: f32, y: f32) -> f32 {
+        self.0[6] * x + self.0[7] * y + self.0[8]
+    }
+
+    /// Maps a point, or `None` when it falls at or behind the viewer.
+    pub fn map(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let m = &self.0;
+        let w = self.weight(x, y);
+        if w <= 1e-6 { return None; }
+        Some(((m[0] * x + m[1] * y + m[2]) / w, (m[3] * x + m[4] * y + m[5]) / w))
+    }
+}
+
+/// Where a node with `rotate_x`/`rotate_y` ends up.
+#[derive(Clone, Copy)]
+pub struct Projection {
+    /// Maps the node's flat (2D-transformed) rendering onto its projected position.
+    pub map: Homography,
+    /// Whether the node has turned its back to the viewer.
+    pub back_facing: bool,
+}
+
+/// Projection of a 3D-rotated node, or `None` for a flat one. `placement` maps the node
+/// into its parent (layout offset and 2D transform), `parent` maps the parent onto the
+/// canvas, and `parent_perspective` is the parent's `perspective` with its center.
+pub fn projection(style: &StyleConfig, w: f32, h: f32, placement: Transform, parent: Transform, parent_perspective: Option<(f32, f32, f32)>) -> Option<Projection> {
+    let (rx, ry) = (style.rotate_x.unwrap_or(0.0), style.rotate_y.unwrap_or(0.0));
+    if rx == 0.0 && ry == 0.0 { return None; }
+
+    let (ox, oy) = transform_origin(style, w, h);
+    let mut local = mul4(&affine4(Transform::from_translate(ox, oy)), &rotate4(rx, ry));
+    // A node's own perspective wins over its parent's.
+    let perspective = match style.perspective.filter(|d| *d > 0.0) {
+        Some(d) => {
+            local = mul4(&perspective4(d, ox, oy), &local);
+            None
+        }
+        None => parent_perspective,
+    };
+    local = mul4(&local, &affine4(Transform::from_translate(-ox, -oy)));
+
+    let mut in_parent = mul4(&affine4(placement), &local);
+    if let Some((d, cx, cy)) = perspective.filter(|(d, ..)| *d > 0.0) {
+        in_parent = mul4(&perspective4(d, cx, cy), &in_parent);
+    }
+    let projected = Homography::from_transform(parent).concat(&Homography::from_plane(&in_parent));
+    let flat = Homography::from_transform(parent.pre_concat(placement));
+    let map = projected.concat(&flat.invert()?);
+
+    // The node faces away when projecting flips the winding of its corners.
+    let signed_area = |hg: &Homography| -> Option<f32> {
+        let p = [(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)].map(|(x, y)| hg.map(x, y));
+        let p = p.into_iter().collect::<Option<Vec<_>>>()?;
+        Some((0..4).map(|i| { let (a, b) = (p[i], p[(i + 1) % 4]); a.0 * b.1 - b.0 * a.1 }).sum())
+    };
+    let back_facing = match (signed_area(&projected), signed_area(&flat)) {
+        (Some(a), Some(b)) => a * b < 0.0,
+        _ => false,
+    };
+    Some(Projection { map, back_facing })
+}
+
+/// Draws `src`, whose top-left pixel sits at `src_origin` on the flat canvas, through
+/// `map` into the empty `dest` at `dest_origin`. Each destination pixel center is
+/// mapped back and sampled bilinearly, with transparency outside `src`.
+pub fn warp(src: &Pixmap, src_origin: (i32, i32), map: &Homography, dest: &mut Pixmap, dest_origin: (i32, i32)) {
+    let Some(inverse) = map.invert() else { return };
+    let m = &inverse.0;
+    let (sw, sh) = (src.width() as i32, src.height() as i32);
+    let data = src.data();
+    let texel = |x: i32, y: i32| -> [f32; 4] {
+        if x < 0 || y < 0 || x >= sw || y >= sh { return [0.0; 4]; }
+        let i = ((y * sw + x) * 4) as usize;
+        [data[i] as f32, data[i + 1] as f32, data[i + 2] as f32, data[i + 3] as f32]
+    };
+
+    let dw = dest.width() as usize;
+    for (i, out) in dest.data_mut().chunks_exact_mut(4).enumerate() {
+        let px = (i % dw) as f32 + 0.5 + dest_origin.0 as f32;
+        let py = (i / dw) as f32 + 0.5 + dest_origin.1 as f32;
+        let w = m[6] * px + m[7] * py + m[8];
+        if w.abs() < 1e-9 { continue; }
+        let (sx, sy) = ((m[0] * px + m[1] * py + m[2]) / w, (m[3] * px + m[4] * py + m[5]) / w);
+        // The inverse also reaches the mirror image behind the viewer; skip it.
+        if map.weight(sx, sy) <= 0.0 { continue; }
+
+        let (u, v) = (sx - src_origin.0 as f32 - 0.5, sy - src_origin.1 as f32 - 0.5);
+        let (x0, y0) = (u.floor(), v.floor());
+        if x0 < -1.0 || y0 < -1.0 || x0 >= sw as f32 || y0 >= sh as f32 { continue; }
+        let (fx, fy) = (u - x0, v - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let (a, b, c, d) = (texel(x0, y0), texel(x0 + 1, y0), texel(x0, y0 + 1), texel(x0 + 1, y0 + 1));
+        for k in 0..4 {
+            let top = a[k] + (b[k] - a[k]) * fx;
+            let bottom = c[k] + (d[k] - c[k]) * fx;
+            out[k] = (top + (bottom - top) * fy + 0.5) as u8;
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use taffy::prelude::*;
+use crate::cascade::apply_classes;
+use crate::engine::{validate_tags, EngineCore};
+use crate::error::{RenderError, RenderWarning};
+use crate::layout::{build_taffy, node_style, LayoutContext};
+use crate::text::TextStyle;
+use crate::types::{SceneNode, StyleConfig};
+
+/// An edit to a `RetainedScene`. Nodes are addressed by their `id`.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Patch {
+    /// Merges `style` into the node's style; properties it leaves out keep their values.
+    SetStyle { id: String, style: Box<StyleConfig> },
+    SetText { id: String, text: String },
+    /// Inserts `node` among `parent`'s children at `index`, or last.
+    InsertChild { parent: String, index: Option<usize>, node: Box<SceneNode> },
+    /// Removes the node from its parent.
+    RemoveChild { id: String },
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Patches {
+    One(Patch),
+    Many(Vec<Patch>),
+}
+
+/// How to take back a patch's change to the tree of a `RetainedScene`.
+enum Undo {
+    Style { path: Vec<usize>, style: Box<StyleConfig> },
+    Text { path: Vec<usize>, text: Option<String> },
+    Insert { parent: Vec<usize>, index: usize, had_children: bool },
+    Remove { parent: Vec<usize>, index: usize, node: Box<SceneNode> },
+}
+
+/// The Taffy tree of a `RetainedScene` and the viewport it was built for.
+struct Layout {
+    taffy: Taffy,
+    root: Node,
+    viewport: Size<f32>,
+}
+
+/// A scene kept between frames and edited with patches, so that unchanged
+/// parts skip parsing, and layout is only recomputed for the subtrees a patch
+/// dirties.
+pub struct RetainedScene {
+    root: SceneNode,
+    scene_styles: HashMap<String, StyleConfig>,
+    /// Child-index path from the root to each node with an `id`.
+    ids: HashMap<String, Vec<usize>>,
+    layout: Option<Layout>,
+    /// Warnings from resolving classes, reported again with every frame.
+    warnings: Vec<RenderWarning>,
+}
+
+impl RetainedScene {
+    /// Parses a scene and resolves its classes, as `EngineCore::render` would.
+    pub fn parse(engine: &EngineCore, json_input: &str) -> Result<Self, RenderError> {
+        let mut root: SceneNode = serde_json::from_str(json_input)?;
+        validate_tags(&root)?;
+        let scene_styles = root.styles.take().unwrap_or_default();
+        let mut warnings = vec![];
+        resolve_classes(engine, &scene_styles, &mut root, &mut warnings);
+        let mut scene = RetainedScene { root, scene_styles, ids: HashMap::new(), layout: None, warnings };
+        scene.reindex();
+        Ok(scene)
+    }
+
+    pub fn root(&self) -> &SceneNode {
+        &self.root
+    }
+
+    pub(crate) fn warnings(&self) -> &[RenderWarning] {
+        &self.warnings
+    }
+
+    /// Ids of all nodes that can be patched.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.ids.keys().map(String::as_str)
+    }
+
+    /// Applies one patch, or a JSON array of them in order. Either all of
+    /// them apply or, if one fails, none do.
+    pub fn patch_json(&mut self, engine: &EngineCore, json_input: &str) -> Result<(), RenderError> {
+        match serde_json::from_str(json_input)? {
+            Patches::One(patch) => self.patch(engine, patch),
+            Patches::Many(patches) => self.patch_all(engine, patches),
+        }
+    }
+
+    /// Applies one patch. If it fails, the scene is left as it was.
+    pub fn patch(&mut self, engine: &EngineCore, patch: Patch) -> Result<(), RenderError> {
+        self.patch_all(engine, [patch])
+    }
+
+    /// Applies `patches` in order, taking back the ones already applied if one
+    /// fails. The layout is rebuilt after a failure rather than unwound, since
+    /// it can fail part way through an update.
+    pub fn patch_all(&mut self, engine: &EngineCore, patches: impl IntoIterator<Item = Patch>) -> Result<(), RenderError> {
+        let warnings = self.warnings.len();
+        let mut undo = vec![];
+        for patch in patches {
+            if let Err(e) = self.apply(engine, patch, &mut undo) {
+                if !undo.is_empty() {
+                    for step in undo.into_iter().rev() { self.undo(step); }
+                    self.reindex();
+                    self.layout = None;
+                }
+                self.warnings.truncate(warnings);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies one patch, recording in `undo` how to take back its change to
+    /// the tree as soon as it is made.
+    fn apply(&mut self, engine: &EngineCore, patch: Patch, undo: &mut Vec<Undo>) -> Result<(), RenderError> {
+        match patch {
+            Patch::SetStyle { id, style } => {
+                let path = self.path(&id)?;
+                let node = node_at_mut(&mut self.root, &path);
+                let before = node.style.clone();
+                node.style.apply(&style);
+                let text_changed = before.font_family != node.style.font_family
+                    || before.font_size != node.style.font_size
+                    || before.line_height != node.style.line_height
+                    || before.letter_spacing != node.style.letter_spacing;
+                undo.push(Undo::Style { path: path.clone(), style: Box::new(before) });
+                if text_changed {
+                    // Descendants measure text and resolve `em` with the new values.
+                    self.rebuild(engine, &path)
+                } else {
+                    self.restyle(engine, &path)
+                }
+            }
+            Patch::SetText { id, text } => {
+                let path = self.path(&id)?;
+                let before = node_at_mut(&mut self.root, &path).text.replace(text);
+                undo.push(Undo::Text { path: path.clone(), text: before });
+                self.rebuild(engine, &path)
+            }
+            Patch::InsertChild { parent, index, mut node } => {
+                validate_tags(&node)?;
+                let path = self.path(&parent)?;
+                resolve_classes(engine, &self.scene_styles, &mut node, &mut self.warnings);
+                let parent_node = node_at_mut(&mut self.root, &path);
+                let had_children = parent_node.children.is_some();
+                let children = parent_node.children.get_or_insert_with(Vec::new);
+                let index = index.unwrap_or(children.len()).min(children.len());
+                children.insert(index, *node);
+                undo.push(Undo::Insert { parent: path.clone(), index, had_children });
+                self.reindex();
+                self.insert_layout(engine, &path, index)
+            }
+            Patch::RemoveChild { id } => {
+                let path = self.path(&id)?;
+                let Some((&index, parent)) = path.split_last() else {
+                    return Err(RenderError::InvalidPatch(format!("cannot remove the root node '{}'", id)));
+                };
+                if let Some(children) = &mut node_at_mut(&mut self.root, parent).children {
+                    let node = children.remove(index);
+                    undo.push(Undo::Remove { parent: parent.to_vec(), index, node: Box::new(node) });
+                }
+                self.reindex();
+                self.remove_layout(engine, parent, index)
+            }
+        }
+    }
+
+    /// Takes back one patch's change to the tree.
+    fn undo(&mut self, step: Undo) {
+        match step {
+            Undo::Style { path, style } => node_at_mut(&mut self.root, &path).style = *style,
+            Undo::Text { path, text } => node_at_mut(&mut self.root, &path).text = text,
+            Undo::Insert { parent, index, had_children } => {
+                let parent = node_at_mut(&mut self.root, &parent);
+                if let Some(children) = &mut parent.children { children.remove(index); }
+                if !had_children { parent.children = None; }
+            }
+            Undo::Remove { parent, index, node } => {
+                node_at_mut(&mut self.root, &parent).children.get_or_insert_with(Vec::new).insert(index, *node);
+            }
+        }
+    }
+
+    /// Builds the layout for `viewport` if it is missing or was built for
+    /// another size, then computes it. Returns the scene root with its layout.
+    pub(crate) fn layout(&mut self, engine: &EngineCore, viewport: Size<f32>) -> Result<(&SceneNode, &Taffy, Node), RenderError> {
+        if !matches!(&self.layout, Some(l) if l.viewport == viewport) {
+            let mut taffy = Taffy::new();
+            let ctx = LayoutContext { assets: &engine.assets, fonts: &engine.fonts, viewport };
+            let root = build_taffy(&mut taffy, &self.root, &ctx, &TextStyle::default())?;
+            self.layout = Some(Layout { taffy, root, viewport });
+        }
+        let layout = self.layout.as_mut().expect("layout was just built");
+        layout.taffy.compute_layout(layout.root, Size {
+            width: AvailableSpace::Definite(viewport.width),
+            height: AvailableSpace::Definite(viewport.height),
+        })?;
+        Ok((&self.root, &layout.taffy, layout.root))
+    }
+
+    fn path(&self, id: &str) -> Result<Vec<usize>, RenderError> {
+        self.ids.get(id).cloned().ok_or_else(|| RenderError::UnknownNode(id.to_string()))
+    }
+
+    fn reindex(&mut self) {
+        fn walk(node: &SceneNode, path: &mut Vec<usize>, ids: &mut HashMap<String, Vec<usize>>) {
+            if let Some(id) = &node.id { ids.insert(id.clone(), path.clone()); }
+            for (i, child) in node.children.iter().flatten().enumerate() {
+                path.push(i);
+                walk(child, path, ids);
+                path.pop();
+            }
+        }
+        self.ids.clear();
+        walk(&self.root, &mut vec![], &mut self.ids);
+    }
+
+    /// Updates the Taffy style of the node at `path` in place. Taffy only marks
+    /// it dirty (with its ancestors) if the style actually changed.
+    fn restyle(&mut self, engine: &EngineCore, path: &[usize]) -> Result<(), RenderError> {
+        let Some(layout) = &mut self.layout else { return Ok(()) };
+        let ctx = LayoutContext { assets: &engine.assets, fonts: &engine.fonts, viewport: layout.viewport };
+        let text = text_style_at(&self.root, path);
+        let style = node_style(node_at(&self.root, path), &ctx, &text);
+        let id = layout_at(&layout.taffy, layout.root, path)?;
+        if *layout.taffy.style(id)? != style {
+            layout.taffy.set_style(id, style)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the Taffy subtree of the node at `path` with a fresh one.
+    fn rebuild(&mut self, engine: &EngineCore, path: &[usize]) -> Result<(), RenderError> {
+        let Some((&index, parent)) = path.split_last() else {
+            self.layout = None;
+            return Ok(());
+        };
+        let Some(layout) = &mut self.layout else { return Ok(()) };
+        let ctx = LayoutContext { assets: &engine.assets, fonts: &engine.fonts, viewport: layout.viewport };
+        let fresh = build_taffy(&mut layout.taffy, node_at(&self.root, path), &ctx, &text_style_at(&self.root, parent))?;
+        let parent_id = layout_at(&layout.taffy, layout.root, parent)?;
+        let old = layout.taffy.replace_child_at_index(parent_id, index, fresh)?;
+        remove_subtree(&mut layout.taffy, old)
+    }
+
+    /// Drops the Taffy subtree of a child just removed from `index` under `parent`.
+    fn remove_layout(&mut self, engine: &EngineCore, parent: &[usize], index: usize) -> Result<(), RenderError> {
+        if node_at(&self.root, parent).text.is_some() {
+            return self.rebuild(engine, parent);
+        }
+        let Some(layout) = &mut self.layout else { return Ok(()) };
+        let parent_id = layout_at(&layout.taffy, layout.root, parent)?;
+        let removed = layout.taffy.remove_child_at_index(parent_id, index)?;
+        remove_subtree(&mut layout.taffy, removed)
+    }
+
+    /// Builds the Taffy subtree for a child just inserted at `index` under `parent`.
+    fn insert_layout(&mut self, engine: &EngineCore, parent: &[usize], index: usize) -> Result<(), RenderError> {
+        if node_at(&self.root, parent).text.is_some() {
+            // Text nodes may be laid out as measured leaves, without child layouts.
+            return self.rebuild(engine, parent);
+        }
+        let Some(layout) = &mut self.layout else { return Ok(()) };
+        let parent_id = layout_at(&layout.taffy, layout.root, parent)?;
+        let mut children = layout.taffy.children(parent_id)?;
+        let ctx = LayoutContext { assets: &engine.assets, fonts: &engine.fonts, viewport: layout.viewport };
+        let mut path = parent.to_vec();
+        path.push(index);
+        let child = build_taffy(&mut layout.taffy, node_at(&self.root, &path), &ctx, &text_style_at(&self.root, parent))?;
+        children.insert(index, child);
+        layout.taffy.set_children(parent_id, &children)?;
+        Ok(())
+    }
+}
+
+/// Resolves `node`'s classes, collecting the warnings into `warnings` rather
+/// than the engine's, which are cleared when a frame starts.
+fn resolve_classes(engine: &EngineCore, scene_styles: &HashMap<String, StyleConfig>, node: &mut SceneNode, warnings: &mut Vec<RenderWarning>) {
+    let earlier = engine.warnings.take();
+    apply_classes(engine, scene_styles, node);
+    for warning in engine.warnings.replace(earlier) {
+        if !warnings.contains(&warning) { warnings.push(warning); }
+    }
+}
+
+fn node_at<'a>(root: &'a SceneNode, path: &[usize]) -> &'a SceneNode {
+    path.iter().fold(root, |node, &i| &node.children.as_ref().expect("path follows children")[i])
+}
+
+fn node_at_mut<'a>(root: &'a mut SceneNode, path: &[usize]) -> &'a mut SceneNode {
+    path.iter().fold(root, |node, &i| &mut node.children.as_mut().expect("path follows children")[i])
+}
+
+/// The text style of the node at `path`, inherited from the root down.
+fn text_style_at<'a>(root: &'a SceneNode, path: &[usize]) -> TextStyle<'a> {
+    let mut node = root;
+    let mut text = TextStyle::default().inherit(&root.style);
+    for &i in path {
+        node = &node.children.as_ref().expect("path follows children")[i];
+        text = text.inherit(&node.style);
+    }
+    text
+}
+
+fn layout_at(taffy: &Taffy, root: Node, path: &[usize]) -> Result<Node, RenderError> {
+    path.iter().try_fold(root, |node, &i| Ok(taffy.child_at_index(node, i)?))
+}
+
+/// Frees a detached Taffy subtree.
+fn remove_subtree(taffy: &mut Taffy, node: Node) -> Result<(), RenderError> {
+    for child in taffy.children(node)? {
+        remove_subtree(taffy, child)?;
+    }
+    taffy.remove(node)?;
+    Ok(())
+}
@@ -1,4 +1,46 @@
 use fontdue::{Font, Metrics};
+use crate::types::StyleConfig;
+
+/// Font size used when a node and its ancestors set none.
+pub const DEFAULT_FONT_SIZE: f32 = 32.0;
+
+/// Text properties after CSS-like inheritance: a node's own value, else its
+/// nearest ancestor's.
+#[derive(Clone, Copy, Default)]
+pub struct TextStyle<'a> {
+    pub color: Option<&'a str>,
+    pub font_family: Option<&'a str>,
+    pub font_size: Option<f32>,
+    pub line_height: Option<f32>,
+    pub letter_spacing: Option<f32>,
+    pub text_align: Option<&'a str>,
+}
+
+impl<'a> TextStyle<'a> {
+    /// The style of a node with `style`, inside a parent with this one.
+    pub fn inherit(&self, style: &'a StyleConfig) -> Self {
+        TextStyle {
+            color: style.color.as_deref().or(self.color),
+            font_family: style.font_family.as_deref().or(self.font_family),
+            font_size: style.font_size.or(self.font_size),
+            line_height: style.line_height.or(self.line_height),
+            letter_spacing: style.letter_spacing.or(self.letter_spacing),
+            text_align: style.text_align.as_deref().or(self.text_align),
+        }
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.font_size.unwrap_or(DEFAULT_FONT_SIZE)
+    }
+
+    pub fn line_height(&self) -> f32 {
+        self.line_height.unwrap_or(self.font_size() * 1.2)
+    }
+
+    pub fn letter_spacing(&self) -> f32 {
+        self.letter_spacing.unwrap_or(0.0)
+    }
+}
 
 pub struct TextLine {
     pub chars: Vec<(char, f32)>,
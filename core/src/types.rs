@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::fmt;
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -9,29 +11,308 @@ pub struct GradientConfig {
     pub angle: Option<f32>,
 }
 
+/// A fill or stroke paint: a color string or a gradient.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum PaintConfig {
+    Color(String),
+    Gradient(GradientConfig),
+}
+
+/// A size or offset: pixels as a number, or a string with a unit
+/// (`"50%"`, `"10vw"`, `"5vh"`, `"2em"`) or `"auto"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "LengthValue", into = "LengthValue")]
+pub enum Length {
+    Px(f32),
+    Percent(f32),
+    Vw(f32),
+    Vh(f32),
+    Em(f32),
+    Auto,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum LengthValue {
+    Px(f32),
+    Text(String),
+}
+
+impl std::str::FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "auto" { return Ok(Length::Auto); }
+        let (number, unit): (&str, fn(f32) -> Length) = if let Some(n) = s.strip_suffix('%') { (n, Length::Percent) }
+            else if let Some(n) = s.strip_suffix("vw") { (n, Length::Vw) }
+            else if let Some(n) = s.strip_suffix("vh") { (n, Length::Vh) }
+            else if let Some(n) = s.strip_suffix("em") { (n, Length::Em) }
+            else { (s.strip_suffix("px").unwrap_or(s), Length::Px) };
+        number.trim().parse::<f32>().map(unit).map_err(|_| format!("invalid length \"{}\"", s))
+    }
+}
+
+impl TryFrom<LengthValue> for Length {
+    type Error = String;
+
+    fn try_from(value: LengthValue) -> Result<Self, Self::Error> {
+        match value {
+            LengthValue::Px(px) => Ok(Length::Px(px)),
+            LengthValue::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<Length> for LengthValue {
+    fn from(length: Length) -> Self {
+        match length {
+            Length::Px(px) => LengthValue::Px(px),
+            other => LengthValue::Text(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Length::Px(v) => write!(f, "{}px", v),
+            Length::Percent(v) => write!(f, "{}%", v),
+            Length::Vw(v) => write!(f, "{}vw", v),
+            Length::Vh(v) => write!(f, "{}vh", v),
+            Length::Em(v) => write!(f, "{}em", v),
+            Length::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// One entry of a grid track list.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GridTrack {
+    /// A share of the free space, `"1fr"`.
+    Fr(f32),
+    Length(Length),
+    /// `"repeat(3, 1fr 20px)"`.
+    Repeat(u16, Vec<GridTrack>),
+}
+
+/// A CSS track list such as `"1fr 200px repeat(3, 1fr)"`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct GridTemplate(pub Vec<GridTrack>);
+
+/// Splits `s` on whitespace outside parentheses.
+fn split_tracks(s: &str) -> Result<Vec<&str>, String> {
+    let (mut tokens, mut depth, mut start) = (vec![], 0, None);
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Err(format!("unbalanced ')' in \"{}\"", s)),
+            ')' => depth -= 1,
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(st) = start.take() { tokens.push(&s[st..i]); }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if depth != 0 { return Err(format!("unbalanced '(' in \"{}\"", s)); }
+    tokens.extend(start.map(|st| &s[st..]));
+    Ok(tokens)
+}
+
+impl std::str::FromStr for GridTrack {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(inner) = s.strip_prefix("repeat(").and_then(|r| r.strip_suffix(')')) {
+            let (count, tracks) = inner.split_once(',').ok_or_else(|| format!("invalid track \"{}\"", s))?;
+            let count = count.trim().parse::<u16>().map_err(|_| format!("invalid repeat count in \"{}\"", s))?;
+            let tracks = split_tracks(tracks)?.into_iter().map(|t| match t.parse()? {
+                GridTrack::Repeat(..) => Err(format!("nested repeat in \"{}\"", s)),
+                track => Ok(track),
+            }).collect::<Result<Vec<_>, _>>()?;
+            return Ok(GridTrack::Repeat(count, tracks));
+        }
+        if let Some(fr) = s.strip_suffix("fr") {
+            return fr.parse().map(GridTrack::Fr).map_err(|_| format!("invalid track \"{}\"", s));
+        }
+        s.parse().map(GridTrack::Length)
+    }
+}
+
+impl fmt::Display for GridTrack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridTrack::Fr(v) => write!(f, "{}fr", v),
+            GridTrack::Length(length) => write!(f, "{}", length),
+            GridTrack::Repeat(count, tracks) => write!(f, "repeat({}, {})", count, GridTemplate(tracks.clone())),
+        }
+    }
+}
+
+impl TryFrom<String> for GridTemplate {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        split_tracks(&s)?.into_iter().map(str::parse).collect::<Result<_, _>>().map(GridTemplate)
+    }
+}
+
+impl fmt::Display for GridTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tracks: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        write!(f, "{}", tracks.join(" "))
+    }
+}
+
+impl From<GridTemplate> for String {
+    fn from(template: GridTemplate) -> Self {
+        template.to_string()
+    }
+}
+
+/// One end of a grid placement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GridLine {
+    Auto,
+    /// A 1-based line number; negative numbers count from the end.
+    Index(i16),
+    Span(u16),
+}
+
+/// Where a grid item goes along one axis: `2`, `"1 / 3"`, `"span 2"` or `"2 / span 2"`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "GridLinesValue", into = "String")]
+pub struct GridLines {
+    pub start: GridLine,
+    pub end: GridLine,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum GridLinesValue {
+    Index(i16),
+    Text(String),
+}
+
+impl std::str::FromStr for GridLine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let line = if s == "auto" {
+            Some(GridLine::Auto)
+        } else if let Some(n) = s.strip_prefix("span") {
+            n.trim().parse().ok().filter(|n| *n > 0).map(GridLine::Span)
+        } else {
+            s.parse().ok().filter(|n| *n != 0).map(GridLine::Index)
+        };
+        line.ok_or_else(|| format!("invalid grid line \"{}\"", s))
+    }
+}
+
+impl TryFrom<GridLinesValue> for GridLines {
+    type Error = String;
+
+    fn try_from(value: GridLinesValue) -> Result<Self, Self::Error> {
+        let text = match value {
+            GridLinesValue::Index(i) => i.to_string(),
+            GridLinesValue::Text(text) => text,
+        };
+        let (start, end) = text.split_once('/').unwrap_or((&text, "auto"));
+        Ok(GridLines { start: start.parse()?, end: end.parse()? })
+    }
+}
+
+impl fmt::Display for GridLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridLine::Auto => write!(f, "auto"),
+            GridLine::Index(i) => write!(f, "{}", i),
+            GridLine::Span(n) => write!(f, "span {}", n),
+        }
+    }
+}
+
+impl From<GridLines> for String {
+    fn from(lines: GridLines) -> Self {
+        format!("{} / {}", lines.start, lines.end)
+    }
+}
+
+/// One step of a `filter` list, like a CSS filter function.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum FilterOp {
+    /// Gaussian blur with standard deviation `radius`.
+    Blur { radius: f32 },
+    Grayscale { amount: f32 },
+    Brightness { amount: f32 },
+    Contrast { amount: f32 },
+    Saturate { amount: f32 },
+    Invert { amount: f32 },
+    Sepia { amount: f32 },
+    /// Rotates hues by `angle` degrees, like CSS `hue-rotate()`.
+    HueRotate { angle: f32 },
+    /// Adds `amount` times each color's difference from a Gaussian blur of
+    /// `radius`. Differences of `threshold` (0-255) or less are left alone.
+    UnsharpMask { radius: f32, amount: f32, threshold: Option<f32> },
+    /// Fades to `color` (black by default) towards the corners of the node's
+    /// box. Distances run from 0 at the centre to 1 at the corners; the fade
+    /// starts at `radius` (0.5) and is complete `softness` (0.5) further out.
+    Vignette { radius: Option<f32>, softness: Option<f32>, color: Option<String> },
+    /// Monochrome noise of strength `amount` (0-1), fixed to the canvas
+    /// pixel grid. The same `seed` and `frame` always give the same grain;
+    /// pass the frame number to animate it.
+    FilmGrain { amount: f32, seed: Option<u32>, frame: Option<u32> },
+    /// Moves the red channel by (`offsetX`, `offsetY`) canvas pixels and the
+    /// blue channel by the opposite, leaving green in place.
+    ChromaticAberration { offset_x: f32, offset_y: f32 },
+}
+
 #[derive(Deserialize, Serialize, Debug, Default, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct StyleConfig {
     // Layout
-    pub width: Option<f32>,
-    pub height: Option<f32>,
+    pub width: Option<Length>,
+    pub height: Option<Length>,
+    pub min_width: Option<Length>,
+    pub min_height: Option<Length>,
+    pub max_width: Option<Length>,
+    pub max_height: Option<Length>,
     pub aspect_ratio: Option<f32>,
     pub flex: Option<f32>,
+    pub flex_shrink: Option<f32>,
+    pub flex_basis: Option<Length>,
     pub flex_direction: Option<String>, 
+    pub flex_wrap: Option<String>,
     pub justify_content: Option<String>,
     pub align_items: Option<String>,
-    pub margin: Option<f32>,
-    pub margin_top: Option<f32>,
-    pub margin_bottom: Option<f32>,
-    pub margin_left: Option<f32>,
-    pub margin_right: Option<f32>,
-    pub padding: Option<f32>,
+    pub align_self: Option<String>,
+    pub align_content: Option<String>,
+    pub gap: Option<Length>,
+    pub row_gap: Option<Length>,
+    pub column_gap: Option<Length>,
+    pub margin: Option<Length>,
+    pub margin_top: Option<Length>,
+    pub margin_bottom: Option<Length>,
+    pub margin_left: Option<Length>,
+    pub margin_right: Option<Length>,
+    pub padding: Option<Length>,
     pub position: Option<String>,
-    pub top: Option<f32>,
-    pub left: Option<f32>,
-    pub right: Option<f32>,
-    pub bottom: Option<f32>,
+    pub top: Option<Length>,
+    pub left: Option<Length>,
+    pub right: Option<Length>,
+    pub bottom: Option<Length>,
     pub z_index: Option<i32>,
+    pub display: Option<String>,
+    pub grid_template_columns: Option<GridTemplate>,
+    pub grid_template_rows: Option<GridTemplate>,
+    pub grid_column: Option<GridLines>,
+    pub grid_row: Option<GridLines>,
     pub overflow: Option<String>,
 
     // Visuals
@@ -53,6 +334,9 @@ pub struct StyleConfig {
     pub stroke_line_join: Option<String>,
     pub stroke_dash_array: Option<Vec<f32>>,
     pub stroke_dash_offset: Option<f32>,
+    pub trim_start: Option<f32>,
+    pub trim_end: Option<f32>,
+    pub trim_offset: Option<f32>,
 
     // Filters
     pub grayscale: Option<f32>,
@@ -62,12 +346,24 @@ pub struct StyleConfig {
     pub blur: Option<f32>,
     pub invert: Option<f32>,
     pub sepia: Option<f32>,
+    /// Filters applied in order after the shorthands above.
+    pub filter: Option<Vec<FilterOp>>,
+    /// Filters applied to what is already drawn behind the node, seen through its shape.
+    pub backdrop_filter: Option<Vec<FilterOp>>,
+    /// Name of a LUT loaded with `load_lut`, applied after the filters.
+    pub lut: Option<String>,
+    /// How much of the graded color to use, 0-1 (default 1).
+    pub lut_intensity: Option<f32>,
+    /// `tetrahedral` (default) or `trilinear`.
+    pub lut_interpolation: Option<String>,
 
     // Shadows
     pub shadow_color: Option<String>,
     pub shadow_blur: Option<f32>,
     pub shadow_offset_x: Option<f32>,
     pub shadow_offset_y: Option<f32>,
+    pub shadow_spread: Option<f32>,
+    pub shadow_inset: Option<bool>,
     
     // Text
     pub color: Option<String>,
@@ -85,16 +381,85 @@ pub struct StyleConfig {
     pub scale: Option<f32>,
     pub skew_x: Option<f32>,
     pub skew_y: Option<f32>,
+    pub scale_x: Option<f32>,
+    pub scale_y: Option<f32>,
+    pub translate_x: Option<f32>,
+    pub translate_y: Option<f32>,
+    pub matrix: Option<[f32; 6]>,
+    pub transform_origin: Option<String>,
+    pub rotate_x: Option<f32>,
+    pub rotate_y: Option<f32>,
+    pub perspective: Option<f32>,
+    pub backface_visibility: Option<String>,
+}
+
+impl StyleConfig {
+    /// Overlays every property `other` sets onto `self`.
+    pub fn apply(&mut self, other: &StyleConfig) {
+        // Destructuring without `..` makes a new field a compile error until it is listed here.
+        macro_rules! overlay {
+            ($($field:ident),* $(,)?) => {
+                let StyleConfig { $($field),* } = other;
+                $(if $field.is_some() { self.$field = $field.clone(); })*
+            };
+        }
+        overlay!(
+            width, height, min_width, min_height, max_width, max_height, aspect_ratio, flex,
+            flex_shrink, flex_basis, flex_direction, flex_wrap, justify_content, align_items,
+            align_self, align_content, gap, row_gap, column_gap, margin, margin_top, margin_bottom,
+            margin_left, margin_right, padding, position, top, left, right, bottom, z_index,
+            display, grid_template_columns, grid_template_rows, grid_column, grid_row, overflow,
+            background_color, background_gradient, border_radius, border_top_left_radius,
+            border_top_right_radius, border_bottom_left_radius, border_bottom_right_radius,
+            border_color, border_width, opacity, blend_mode, mask_mode, stroke_line_cap,
+            stroke_line_join, stroke_dash_array, stroke_dash_offset, trim_start, trim_end,
+            trim_offset, grayscale, brightness, contrast, saturation, blur, invert, sepia, filter,
+            backdrop_filter, lut, lut_intensity, lut_interpolation, shadow_color, shadow_blur, shadow_offset_x, shadow_offset_y, shadow_spread,
+            shadow_inset, color, font_size, font_family, text_align, line_height, letter_spacing,
+            object_fit, rotate, scale, skew_x, skew_y, scale_x, scale_y, translate_x, translate_y,
+            matrix, transform_origin, rotate_x, rotate_y, perspective, backface_visibility
+        );
+    }
+}
+
+/// One or more class names: `"card title"` or `["card", "title"]`.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ClassList {
+    Names(String),
+    List(Vec<String>),
+}
+
+impl ClassList {
+    pub fn names(&self) -> Vec<&str> {
+        match self {
+            ClassList::Names(names) => names.split_whitespace().collect(),
+            ClassList::List(list) => list.iter().map(String::as_str).collect(),
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SceneNode {
     pub tag: String,
+    /// Names the node for patches to a retained scene.
+    pub id: Option<String>,
     pub text: Option<String>,
     pub src: Option<String>,
     pub d: Option<String>,
+    pub fill: Option<PaintConfig>,
+    pub stroke: Option<PaintConfig>,
+    pub stroke_width: Option<f32>,
+    pub fill_rule: Option<String>,
+    pub fill_opacity: Option<f32>,
+    pub stroke_opacity: Option<f32>,
+    #[serde(default)]
     pub style: StyleConfig,
+    /// Classes whose styles apply under `style`, later names winning.
+    pub class: Option<ClassList>,
+    /// Named classes for the whole scene. Only read on the root node.
+    pub styles: Option<HashMap<String, StyleConfig>>,
     pub children: Option<Vec<SceneNode>>,
     pub mask: Option<Box<SceneNode>>,
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 
-use tiny_skia::{Color, BlendMode, Point};
-use svgtypes::{PathParser, PathSegment};
+use tiny_skia::{Color, BlendMode, Path, PathBuilder, PathSegment, Point, StrokeDash};
+use svgtypes::{SimplePathSegment, SimplifyingPathParser};
 
 pub fn parse_color(hex: &str) -> Color {
     let hex = hex.trim_start_matches('#');
@@ -74,52 +74,122 @@ fn quad_length(p1: Point, p2: Point, p3: Point) -> f32 {
     curve_length(p1, p2_c, p3_c, p3)
 }
 
-pub fn calculate_path_length(d: &str) -> f32 {
+/// Parses SVG path data into a tiny-skia path.
+/// Relative commands, H/V, smooth curves and arcs are normalized by svgtypes
+/// into absolute lines, quadratics and cubics.
+pub fn parse_svg_path(d: &str) -> Option<Path> {
+    let mut pb = PathBuilder::new();
+    for segment in SimplifyingPathParser::from(d) {
+        match segment {
+            Ok(SimplePathSegment::MoveTo { x, y }) => pb.move_to(x as f32, y as f32),
+            Ok(SimplePathSegment::LineTo { x, y }) => pb.line_to(x as f32, y as f32),
+            Ok(SimplePathSegment::CurveTo { x1, y1, x2, y2, x, y }) => {
+                pb.cubic_to(x1 as f32, y1 as f32, x2 as f32, y2 as f32, x as f32, y as f32)
+            },
+            Ok(SimplePathSegment::Quadratic { x1, y1, x, y }) => pb.quad_to(x1 as f32, y1 as f32, x as f32, y as f32),
+            Ok(SimplePathSegment::ClosePath) => pb.close(),
+            // Like browsers, render everything up to the first malformed segment.
+            Err(_) => break,
+        }
+    }
+    pb.finish()
+}
+
+pub fn path_length(path: &Path) -> f32 {
     let mut length = 0.0;
     let mut curr = Point::from_xy(0.0, 0.0);
     let mut start = Point::from_xy(0.0, 0.0);
 
-    for segment in PathParser::from(d) {
+    for segment in path.segments() {
         match segment {
-            Ok(PathSegment::MoveTo { abs, x, y }) => {
-                let p = if abs { Point::from_xy(x as f32, y as f32) } else { Point::from_xy(curr.x + x as f32, curr.y + y as f32) };
+            PathSegment::MoveTo(p) => {
                 curr = p;
                 start = p;
             },
-            Ok(PathSegment::LineTo { abs, x, y }) => {
-                let p = if abs { Point::from_xy(x as f32, y as f32) } else { Point::from_xy(curr.x + x as f32, curr.y + y as f32) };
+            PathSegment::LineTo(p) => {
                 length += dist(curr, p);
                 curr = p;
             },
-            Ok(PathSegment::HorizontalLineTo { abs, x }) => {
-                 let p = if abs { Point::from_xy(x as f32, curr.y) } else { Point::from_xy(curr.x + x as f32, curr.y) };
-                 length += dist(curr, p);
-                 curr = p;
-            },
-            Ok(PathSegment::VerticalLineTo { abs, y }) => {
-                 let p = if abs { Point::from_xy(curr.x, y as f32) } else { Point::from_xy(curr.x, curr.y + y as f32) };
-                 length += dist(curr, p);
-                 curr = p;
-            },
-            Ok(PathSegment::CurveTo { abs, x1, y1, x2, y2, x, y }) => {
-                let p1 = if abs { Point::from_xy(x1 as f32, y1 as f32) } else { Point::from_xy(curr.x + x1 as f32, curr.y + y1 as f32) };
-                let p2 = if abs { Point::from_xy(x2 as f32, y2 as f32) } else { Point::from_xy(curr.x + x2 as f32, curr.y + y2 as f32) };
-                let p3 = if abs { Point::from_xy(x as f32, y as f32) } else { Point::from_xy(curr.x + x as f32, curr.y + y as f32) };
-                length += curve_length(curr, p1, p2, p3);
-                curr = p3;
+            PathSegment::QuadTo(p1, p) => {
+                length += quad_length(curr, p1, p);
+                curr = p;
             },
-            Ok(PathSegment::Quadratic { abs, x1, y1, x, y }) => {
-                let p1 = if abs { Point::from_xy(x1 as f32, y1 as f32) } else { Point::from_xy(curr.x + x1 as f32, curr.y + y1 as f32) };
-                let p2 = if abs { Point::from_xy(x as f32, y as f32) } else { Point::from_xy(curr.x + x as f32, curr.y + y as f32) };
-                length += quad_length(curr, p1, p2);
-                curr = p2;
+            PathSegment::CubicTo(p1, p2, p) => {
+                length += curve_length(curr, p1, p2, p);
+                curr = p;
             },
-            Ok(PathSegment::ClosePath { .. }) => {
+            PathSegment::Close => {
                 length += dist(curr, start);
                 curr = start;
             }
-            _ => {}
         }
     }
     length
 }
+
+/// Measures SVG path data exactly as `render::generate_path` draws it.
+pub fn calculate_path_length(d: &str) -> f32 {
+    parse_svg_path(d).map(|p| path_length(&p)).unwrap_or(0.0)
+}
+
+/// Splits a path into its contours, one per `MoveTo`.
+fn contours(path: &Path) -> Vec<Path> {
+    let mut out = Vec::new();
+    let mut pb = PathBuilder::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => {
+                if let Some(contour) = std::mem::take(&mut pb).finish() { out.push(contour); }
+                pb.move_to(p.x, p.y);
+            },
+            PathSegment::LineTo(p) => pb.line_to(p.x, p.y),
+            PathSegment::QuadTo(p1, p) => pb.quad_to(p1.x, p1.y, p.x, p.y),
+            PathSegment::CubicTo(p1, p2, p) => pb.cubic_to(p1.x, p1.y, p2.x, p2.y, p.x, p.y),
+            PathSegment::Close => pb.close(),
+        }
+    }
+    out.extend(pb.finish());
+    out
+}
+
+/// The part of `path` between `start` and `end` (fractions of its total length),
+/// moved along by `offset` and wrapping past the end, like Lottie's trim paths.
+/// Contours are measured one after another as a single line.
+pub fn trim_path(path: &Path, start: f32, end: f32, offset: f32) -> Option<Path> {
+    let (start, end) = (start.clamp(0.0, 1.0), end.clamp(0.0, 1.0));
+    let (start, end) = if start > end { (end, start) } else { (start, end) };
+    if end - start >= 1.0 { return Some(path.clone()); }
+    if end <= start { return None; }
+
+    let contours = contours(path);
+    let lengths: Vec<f32> = contours.iter().map(path_length).collect();
+    let total: f32 = lengths.iter().sum();
+    if total <= 0.0 { return None; }
+
+    let shift = offset.rem_euclid(1.0);
+    let (s, e) = ((start + shift) * total, (end + shift) * total);
+    let spans = [(s, e), (s - total, e - total)];
+
+    let mut pb = PathBuilder::new();
+    let mut at = 0.0;
+    for (contour, len) in contours.iter().zip(lengths) {
+        for (a, b) in spans {
+            let (a, b) = ((a - at).max(0.0), (b - at).min(len));
+            if b <= a { continue; }
+            // Our length estimate can fall slightly short of tiny-skia's, so a span
+            // that reaches the end of the contour is left open-ended.
+            let reaches_end = b >= len * 0.999;
+            if a == 0.0 && reaches_end {
+                pb.push_path(contour);
+                continue;
+            }
+            let gap = len * 2.0 + 1.0;
+            let on = if reaches_end { gap } else { b - a };
+            if let Some(piece) = StrokeDash::new(vec![on, gap], -a).and_then(|dash| contour.dash(&dash, 1.0)) {
+                pb.push_path(&piece);
+            }
+        }
+        at += len;
+    }
+    pb.finish()
+}
@@ -0,0 +1,146 @@
+use wasm_bindgen::prelude::*;
+use crate::engine::EngineCore;
+use crate::error::{RenderError, RenderWarning};
+use crate::scene::RetainedScene;
+use crate::utils::calculate_path_length;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct AmethystEngine {
+    core: EngineCore,
+    last_warnings: Vec<RenderWarning>,
+    scene: Option<RetainedScene>,
+    /// Pixels of the last `*_into_frame` render, reused across frames.
+    frame: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl AmethystEngine {
+    pub fn new() -> AmethystEngine {
+        AmethystEngine::default()
+    }
+    
+    pub fn load_font(&mut self, name: &str, data: &[u8]) -> Result<(), JsValue> {
+        self.core.load_font(name, data).map_err(|e| JsValue::from_str(&e))
+    }
+    
+    /// Registers named classes, given as a JSON object of class name -> style,
+    /// for all later frames.
+    pub fn register_styles(&mut self, json_input: &str) -> Result<(), JsValue> {
+        let styles = serde_json::from_str(json_input).map_err(|e| JsValue::from_str(&RenderError::from(e).to_string()))?;
+        self.core.register_styles(styles);
+        Ok(())
+    }
+
+    pub fn load_asset(&mut self, id: &str, data: &[u8]) -> Result<(), JsValue> {
+        self.core.load_asset(id, data).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn load_lut(&mut self, name: &str, data: &[u8]) -> Result<(), JsValue> {
+        self.core.load_lut(name, data).map_err(|e| JsValue::from_str(&e))
+    }
+
+    pub fn load_asset_raw(&mut self, id: &str, data: &[u8], width: u32, height: u32) -> Result<(), JsValue> {
+        self.core.load_asset_raw(id, data, width, height).map_err(|e| JsValue::from_str(e.as_str()))
+    }
+
+    /// Renders one frame. Throws with a descriptive message if the scene is
+    /// malformed; non-fatal issues are available from `warnings()` afterwards.
+    pub fn render(&mut self, json_input: &str, width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        let frame = self.core.render(json_input, width, height).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.last_warnings = frame.warnings;
+        Ok(frame.pixels)
+    }
+
+    /// Renders a MessagePack-encoded scene, skipping JSON on both sides.
+    pub fn render_binary(&mut self, data: &[u8], width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        let frame = self.core.render_binary(data, width, height).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.last_warnings = frame.warnings;
+        Ok(frame.pixels)
+    }
+
+    /// Renders a MessagePack-encoded scene into the engine's frame buffer,
+    /// which stays allocated across frames. Read it without a copy through
+    /// `frame_ptr` and `frame_len` as a view on the wasm memory; the view is
+    /// only valid until the next call.
+    pub fn render_into_frame(&mut self, data: &[u8], width: u32, height: u32) -> Result<(), JsValue> {
+        resize_frame(&mut self.frame, width, height);
+        self.last_warnings = self.core.render_binary_into(data, width, height, &mut self.frame).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(())
+    }
+
+    /// `render_into_frame` for a JSON scene.
+    pub fn render_json_into_frame(&mut self, json_input: &str, width: u32, height: u32) -> Result<(), JsValue> {
+        resize_frame(&mut self.frame, width, height);
+        self.last_warnings = self.core.render_into(json_input, width, height, &mut self.frame).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(())
+    }
+
+    pub fn frame_ptr(&self) -> *const u8 {
+        self.frame.as_ptr()
+    }
+
+    pub fn frame_len(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Lays out a JSON scene without rendering it. Returns a JSON array of
+    /// node boxes (`id`, `tag`, `parent`, `x`, `y`, `width`, `height`,
+    /// `transform` and, for text, `lines`) in document order.
+    pub fn compute_layout(&self, json_input: &str, width: u32, height: u32) -> Result<String, JsValue> {
+        let boxes = self.core.compute_layout(json_input, width, height).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        serde_json::to_string(&boxes).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Keeps a scene for `patch_scene` and `render_retained`, replacing any
+    /// previously uploaded one.
+    pub fn upload_scene(&mut self, json_input: &str) -> Result<(), JsValue> {
+        self.scene = Some(RetainedScene::parse(&self.core, json_input).map_err(|e| JsValue::from_str(&e.to_string()))?);
+        Ok(())
+    }
+
+    /// Applies a patch, or a JSON array of patches, to the uploaded scene.
+    /// If one fails, the scene is left as it was.
+    pub fn patch_scene(&mut self, json_input: &str) -> Result<(), JsValue> {
+        let scene = self.scene.as_mut().ok_or_else(|| JsValue::from_str("No scene uploaded"))?;
+        scene.patch_json(&self.core, json_input).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Renders the uploaded scene, like `render`.
+    pub fn render_retained(&mut self, width: u32, height: u32) -> Result<Vec<u8>, JsValue> {
+        let scene = self.scene.as_mut().ok_or_else(|| JsValue::from_str("No scene uploaded"))?;
+        let frame = self.core.render_retained(scene, width, height).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.last_warnings = frame.warnings;
+        Ok(frame.pixels)
+    }
+
+    /// Renders the uploaded scene into the frame buffer, like `render_into_frame`.
+    pub fn render_retained_into_frame(&mut self, width: u32, height: u32) -> Result<(), JsValue> {
+        let scene = self.scene.as_mut().ok_or_else(|| JsValue::from_str("No scene uploaded"))?;
+        resize_frame(&mut self.frame, width, height);
+        self.last_warnings = self.core.render_retained_into(scene, width, height, &mut self.frame).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(())
+    }
+
+    /// Warnings from the most recent `render` call, as a JSON array.
+    pub fn warnings(&self) -> String {
+        serde_json::to_string(&self.last_warnings).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    pub fn measure_path(&self, d: &str) -> f32 {
+        calculate_path_length(d)
+    }
+}
+
+/// Sizes `frame` for a `width`x`height` render. Sizes that overflow are left
+/// for the engine to reject.
+fn resize_frame(frame: &mut Vec<u8>, width: u32, height: u32) {
+    let len = (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(4)).unwrap_or(0);
+    frame.resize(len, 0);
+}
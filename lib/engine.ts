@@ -2,19 +2,28 @@ import { spawn } from "bun";
 import os from "node:os";
 import pc from "picocolors";
 import cliProgress from "cli-progress";
-import { getRawEngine } from "./wasm.js";
-import { renderSingleFrame } from "./renderer.js";
-import type { RenderConfig, SceneNode } from "./types.js";
+import { getEngine, getRawEngine } from "./wasm.js";
+import { renderFrameView, renderSingleFrame } from "./renderer.js";
+import type { LayoutBox, RenderConfig, SceneNode } from "./types.js";
 
 export * from "./hooks.js";
 export { startPreview } from "./server.js";
-export { renderSingleFrame };
+export { renderFrameView, renderSingleFrame };
 
 export function measurePath(d: string): number {
   const engine = getRawEngine();
   return engine ? engine.measure_path(d) : 0;
 }
 
+/**
+ * Lays out a scene without rendering it: every node's box in canvas
+ * coordinates, in document order, for hit regions, overlays and the like.
+ */
+export async function computeLayout(sceneGraph: SceneNode, config: RenderConfig): Promise<LayoutBox[]> {
+  const engine = await getEngine(config);
+  return JSON.parse(engine.compute_layout(JSON.stringify(sceneGraph), Math.floor(config.width), Math.floor(config.height)));
+}
+
 export async function render<T>(
   sceneComponent: (props: T) => SceneNode,
   config: RenderConfig,
@@ -44,7 +53,8 @@ export async function render<T>(
 
   const ffmpeg = spawn(["ffmpeg", ...ffmpegArgs], { stdin: "pipe", stderr: "pipe" });
   
-  const frameMap = new Map<number, Uint8Array>();
+  // Frames waiting for their turn, with the worker whose buffer they are in.
+  const frameMap = new Map<number, { pixels: Uint8Array; worker: Worker }>();
   let framesWritten = 0;
 
   progressBar.start(totalFrames, 0);
@@ -64,14 +74,23 @@ export async function render<T>(
     worker.onmessage = (event) => {
       const data = event.data;
       if (data.type === "frame") {
-        frameMap.set(data.frame, new Uint8Array(data.pixels));
+        frameMap.set(data.frame, { pixels: new Uint8Array(data.pixels), worker });
         
+        const written: { pixels: Uint8Array; worker: Worker }[] = [];
         while (frameMap.has(framesWritten)) {
-          ffmpeg.stdin.write(frameMap.get(framesWritten)!);
+          const entry = frameMap.get(framesWritten)!;
+          ffmpeg.stdin.write(entry.pixels);
+          written.push(entry);
           frameMap.delete(framesWritten);
           framesWritten++;
           progressBar.update(framesWritten);
         }
+        // Once ffmpeg has the bytes, hand the buffers back for the next frames.
+        if (written.length > 0) {
+          Promise.resolve(ffmpeg.stdin.flush()).then(() => {
+            for (const { pixels, worker } of written) worker.postMessage({ type: "recycle", pixels: pixels.buffer }, [pixels.buffer as any]);
+          });
+        }
       }
       if (data.type === "done") worker.terminate();
       if (data.type === "error") {
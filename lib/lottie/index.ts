@@ -1,7 +1,6 @@
 import { getLottieValue } from "./interpolator.js";
 import { bezierToPath, lottieColorToHex } from "./converter.js";
 import { generatePolystarPath } from "./shapes.js";
-import { measurePath } from "../engine.js"; // Fixed: Changed .ts to .js for ESM compliance
 import type { SceneNode, StyleConfig } from "../types.js";
 import type { LottieJSON, LottieTrimPath, LottiePolystar, LottieLayer } from "./types.js";
 
@@ -73,10 +72,13 @@ function renderLayer(layer: LottieLayer, frame: number): SceneNode | null {
       const tm = layer.shapes.find((s: any) => s.ty === "tm") as LottieTrimPath | undefined;
       let trimStart = 0;
       let trimEnd = 100;
+      let trimOffset = 0;
       
       if (tm) {
           trimStart = getLottieValue(tm.s, frame) ?? 0;
           trimEnd = getLottieValue(tm.e, frame) ?? 100;
+          // Lottie's offset is in degrees around the path.
+          trimOffset = ((getLottieValue(tm.o, frame) ?? 0) / 360) * 100;
       }
 
       for (const item of layer.shapes) {
@@ -120,14 +122,9 @@ function renderLayer(layer: LottieLayer, frame: number): SceneNode | null {
                       pathStyle.strokeLineCap = "round";
                       pathStyle.strokeLineJoin = "round";
                       if (tm) {
-                          const len = measurePath(d);
-                          if (len > 0) {
-                              const s = (trimStart / 100) * len;
-                              const e = (trimEnd / 100) * len;
-                              const visibleLen = Math.abs(e - s);
-                              pathStyle.strokeDashArray = [visibleLen, len];
-                              pathStyle.strokeDashOffset = -s;
-                          }
+                          pathStyle.trimStart = trimStart;
+                          pathStyle.trimEnd = trimEnd;
+                          pathStyle.trimOffset = trimOffset;
                       }
                   }
                   if (currentFill || currentStroke) {
@@ -0,0 +1,128 @@
+/**
+ * MessagePack encoder for scene graphs, read by `AmethystEngine.render_binary`.
+ *
+ * Values are written as `JSON.stringify` would see them, so a scene decodes to
+ * exactly what its JSON form parses to: object keys whose values are
+ * `undefined` or functions are dropped (`null` in arrays), non-finite numbers
+ * become `null`, and `toJSON` is honoured.
+ */
+const textEncoder = new TextEncoder();
+
+class Writer {
+  buffer = new Uint8Array(4096);
+  view = new DataView(this.buffer.buffer);
+  length = 0;
+
+  reserve(bytes: number) {
+    if (this.length + bytes <= this.buffer.length) return;
+    let size = this.buffer.length * 2;
+    while (size < this.length + bytes) size *= 2;
+    const grown = new Uint8Array(size);
+    grown.set(this.buffer.subarray(0, this.length));
+    this.buffer = grown;
+    this.view = new DataView(grown.buffer);
+  }
+
+  u8(value: number) {
+    this.reserve(1);
+    this.buffer[this.length++] = value;
+  }
+
+  /** A type byte followed by a big-endian integer or float of `bytes` bytes. */
+  head(type: number, value: number, bytes: 1 | 2 | 4 | 8, float = false) {
+    this.reserve(1 + bytes);
+    this.buffer[this.length++] = type;
+    const at = this.length;
+    if (float) this.view.setFloat64(at, value);
+    else if (bytes === 1) this.view.setUint8(at, value);
+    else if (bytes === 2) this.view.setUint16(at, value);
+    else if (bytes === 4) this.view.setUint32(at, value);
+    this.length += bytes;
+  }
+
+  /** A container or string header, using the `fix` form when it fits in `fixBits`. */
+  size(fix: number, fixBits: number, type16: number, size: number) {
+    if (size < 1 << fixBits) this.u8(fix | size);
+    else if (size <= 0xffff) this.head(type16, size, 2);
+    else this.head(type16 + 1, size, 4);
+  }
+}
+
+function encodeNumber(w: Writer, n: number) {
+  if (!Number.isFinite(n)) return w.u8(0xc0);
+  if (!Number.isInteger(n)) return w.head(0xcb, n, 8, true);
+  if (n >= 0) {
+    if (n < 0x80) return w.u8(n);
+    if (n <= 0xff) return w.head(0xcc, n, 1);
+    if (n <= 0xffff) return w.head(0xcd, n, 2);
+    if (n <= 0xffffffff) return w.head(0xce, n, 4);
+  } else {
+    if (n >= -32) return w.u8(n & 0xff);
+    if (n >= -0x80) return w.head(0xd0, n & 0xff, 1);
+    if (n >= -0x8000) return w.head(0xd1, n & 0xffff, 2);
+    if (n >= -0x80000000) return w.head(0xd2, n >>> 0, 4);
+  }
+  // Beyond 32 bits; no scene field needs the integer forms there.
+  w.head(0xcb, n, 8, true);
+}
+
+function encodeString(w: Writer, s: string) {
+  // UTF-8 takes at most 3 bytes per UTF-16 code unit.
+  const max = s.length * 3;
+  const headerBytes = max < 32 ? 1 : max <= 0xff ? 2 : max <= 0xffff ? 3 : 5;
+  w.reserve(headerBytes + max);
+  const start = w.length + headerBytes;
+  const { written } = textEncoder.encodeInto(s, w.buffer.subarray(start));
+  const bytes = written ?? 0;
+  // The header size was picked from the worst case; move the bytes if the real length needs less.
+  const actualHeader = bytes < 32 ? 1 : bytes <= 0xff ? 2 : bytes <= 0xffff ? 3 : 5;
+  if (actualHeader !== headerBytes) {
+    w.buffer.copyWithin(w.length + actualHeader, start, start + bytes);
+  }
+  if (bytes < 32) w.u8(0xa0 | bytes);
+  else if (bytes <= 0xff) w.head(0xd9, bytes, 1);
+  else if (bytes <= 0xffff) w.head(0xda, bytes, 2);
+  else w.head(0xdb, bytes, 4);
+  w.length += bytes;
+}
+
+function encodeValue(w: Writer, value: unknown) {
+  if (value !== null && typeof value === "object" && typeof (value as any).toJSON === "function") {
+    value = (value as any).toJSON();
+  }
+  switch (typeof value) {
+    case "number":
+      return encodeNumber(w, value);
+    case "string":
+      return encodeString(w, value);
+    case "boolean":
+      return w.u8(value ? 0xc3 : 0xc2);
+    case "object": {
+      if (value === null) return w.u8(0xc0);
+      if (Array.isArray(value)) {
+        w.size(0x90, 4, 0xdc, value.length);
+        for (const item of value) {
+          encodeValue(w, item === undefined || typeof item === "function" ? null : item);
+        }
+        return;
+      }
+      const entries = Object.entries(value).filter(([, v]) => v !== undefined && typeof v !== "function");
+      w.size(0x80, 4, 0xde, entries.length);
+      for (const [key, v] of entries) {
+        encodeString(w, key);
+        encodeValue(w, v);
+      }
+      return;
+    }
+    default:
+      // undefined, functions and symbols, where JSON.stringify would give null.
+      w.u8(0xc0);
+  }
+}
+
+/** Encodes a scene (or any JSON-compatible value) as MessagePack. */
+export function encodeScene(value: unknown): Uint8Array {
+  const w = new Writer();
+  encodeValue(w, value);
+  return w.buffer.subarray(0, w.length);
+}
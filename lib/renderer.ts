@@ -1,10 +1,13 @@
 import { State } from "./state.js";
-import { getEngine } from "./wasm.js";
+import { frameView, getEngine } from "./wasm.js";
 import { VideoManager } from "./video.js";
+import { encodeScene } from "./msgpack.js";
 import type { RenderConfig, SceneNode } from "./types.js";
 
 const videoManager = new VideoManager();
+const reportedWarnings = new Set<string>();
 
+/** Renders one frame into the engine's frame buffer and returns a view on it, valid until the next render. */
 async function renderRawFrame<T>(
   engine: any,
   sceneComponent: (props: T) => SceneNode,
@@ -25,14 +28,45 @@ async function renderRawFrame<T>(
   }
 
   const sceneGraph = sceneComponent(props);
-  return engine.render(JSON.stringify(sceneGraph), Math.floor(config.width), Math.floor(config.height));
+  engine.render_into_frame(encodeScene(sceneGraph), Math.floor(config.width), Math.floor(config.height));
+
+  // Missing assets/fonts/LUTs don't fail the frame; report each one once per process.
+  for (const warning of JSON.parse(engine.warnings()) as { kind: string; src?: string; family?: string; name?: string }[]) {
+    const key = `${warning.kind}:${warning.src ?? warning.family ?? warning.name}`;
+    if (!reportedWarnings.has(key)) {
+      reportedWarnings.add(key);
+      const message = warning.kind === "missingAsset" ? `Asset "${warning.src}" is not loaded`
+        : warning.kind === "missingClass" ? `Class "${warning.name}" is not defined`
+        : warning.kind === "missingLut" ? `LUT "${warning.name}" is not loaded`
+        : `No font available for "${warning.family}"`;
+      console.warn(`⚠️  ${message}`);
+    }
+  }
+  return frameView(engine);
 }
 
+/** Renders one frame. The returned pixels are the caller's to keep. */
 export async function renderSingleFrame<T>(
   sceneComponent: (props: T) => SceneNode,
   config: RenderConfig,
   frame: number,
   props: T
+): Promise<Uint8Array> {
+  const pixels = await renderFrameView(sceneComponent, config, frame, props);
+  // Motion blur already hands back a fresh buffer.
+  return (config.motionBlurSamples || 0) <= 1 ? pixels.slice() : pixels;
+}
+
+/**
+ * Like `renderSingleFrame`, but without motion blur the result is a
+ * `frameView` on the engine's frame buffer, valid until the next render. Use
+ * it to read or copy a frame out without allocating one per frame.
+ */
+export async function renderFrameView<T>(
+  sceneComponent: (props: T) => SceneNode,
+  config: RenderConfig,
+  frame: number,
+  props: T
 ): Promise<Uint8Array> {
   const engine = await getEngine(config);
   
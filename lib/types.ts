@@ -5,28 +5,87 @@ export interface GradientConfig {
   angle?: number;
 }
 
+/** A fill or stroke: a color or a gradient. */
+export type Paint = string | GradientConfig;
+
+/** Pixels, or a string with a unit: "50%" of the parent, "10vw" / "5vh" of the render size, "2em" of the font size. */
+export type Length = number | `${number}${"%" | "px" | "vw" | "vh" | "em"}` | "auto";
+
+export type JustifyContent = "flexStart" | "flexEnd" | "start" | "end" | "center" | "stretch" |
+                             "spaceBetween" | "spaceAround" | "spaceEvenly";
+
+export type AlignItems = "flexStart" | "flexEnd" | "start" | "end" | "center" | "baseline" | "stretch";
+
+/** One step of a `filter` list, like a CSS filter function. */
+export type FilterOp =
+  /** Gaussian blur; `radius` is the standard deviation in pixels. */
+  | { type: "blur"; radius: number }
+  | { type: "grayscale" | "brightness" | "contrast" | "saturate" | "invert" | "sepia"; amount: number }
+  /** Rotates hues by `angle` degrees, like CSS `hue-rotate()`. */
+  | { type: "hueRotate"; angle: number }
+  /** Sharpens colors by `amount` times their difference from a blur of `radius`; differences up to `threshold` (0-255) are kept. */
+  | { type: "unsharpMask"; radius: number; amount: number; threshold?: number }
+  /**
+   * Fades to `color` (default black) towards the corners of the node's box.
+   * Distance is 0 at the centre and 1 at the corners; the fade starts at
+   * `radius` (0.5) and completes `softness` (0.5) further out.
+   */
+  | { type: "vignette"; radius?: number; softness?: number; color?: string }
+  /** Deterministic monochrome noise; pass `frame: useFrame()` to animate it. */
+  | { type: "filmGrain"; amount: number; seed?: number; frame?: number }
+  /** Moves red by (`offsetX`, `offsetY`) pixels and blue the opposite way. */
+  | { type: "chromaticAberration"; offsetX: number; offsetY: number };
+
 export interface StyleConfig {
   // Layout
-  width?: number;
-  height?: number;
+  width?: Length;
+  height?: Length;
+  minWidth?: Length;
+  minHeight?: Length;
+  maxWidth?: Length;
+  maxHeight?: Length;
   aspectRatio?: number;
   flex?: number;
-  flexDirection?: "row" | "column";
-  justifyContent?: "center" | "spaceBetween" | "flexStart" | "flexEnd";
-  alignItems?: "center" | "flexStart" | "flexEnd";
-  margin?: number;
-  marginTop?: number;
-  marginBottom?: number;
-  marginLeft?: number;
-  marginRight?: number;
-  padding?: number;
+  flexShrink?: number;
+  flexBasis?: Length;
+  flexDirection?: "row" | "column" | "rowReverse" | "columnReverse";
+  flexWrap?: "nowrap" | "wrap" | "wrapReverse";
+  justifyContent?: JustifyContent;
+  alignItems?: AlignItems;
+  /** Overrides the parent's `alignItems` for this node. */
+  alignSelf?: AlignItems;
+  /** Distributes wrapped lines along the cross axis. */
+  alignContent?: JustifyContent;
+  /** Space between items; `rowGap` / `columnGap` override it per axis. */
+  gap?: Length;
+  rowGap?: Length;
+  columnGap?: Length;
+  margin?: Length;
+  marginTop?: Length;
+  marginBottom?: Length;
+  marginLeft?: Length;
+  marginRight?: Length;
+  padding?: Length;
   
   position?: "relative" | "absolute";
-  top?: number;
-  left?: number;
-  right?: number;
-  bottom?: number;
+  top?: Length;
+  left?: Length;
+  right?: Length;
+  bottom?: Length;
+  /**
+   * Paint order among siblings within the nearest stacking context, without changing layout.
+   * Nodes with opacity, a blend mode, a mask, a clip, a filter or a zIndex start their own context.
+   */
   zIndex?: number;
+
+  // Grid
+  display?: "flex" | "grid";
+  /** CSS track lists: "fr", pixel, percentage and "auto" tracks plus repeat(), e.g. "repeat(3, 1fr) 200px". */
+  gridTemplateColumns?: string;
+  gridTemplateRows?: string;
+  /** A line number, or "start / end" with lines or "span n", e.g. 2, "1 / -1", "span 2". */
+  gridColumn?: number | string;
+  gridRow?: number | string;
   
   // Visuals
   backgroundColor?: string;
@@ -52,8 +111,13 @@ export interface StyleConfig {
   strokeLineJoin?: "miter" | "round" | "bevel";
   strokeDashArray?: number[];
   strokeDashOffset?: number;
+  /** Percentages (0-100) of the path length to stroke; the offset moves the span and wraps around. */
+  trimStart?: number;
+  trimEnd?: number;
+  trimOffset?: number;
 
-  // Filters
+  // Filters. The shorthands run first (saturation, grayscale, contrast,
+  // brightness, invert, sepia, blur), then `filter`.
   grayscale?: number;
   brightness?: number;
   contrast?: number;
@@ -61,14 +125,36 @@ export interface StyleConfig {
   blur?: number;
   invert?: number;
   sepia?: number;
+  /** Filters applied to the node's composited layer in the listed order. */
+  filter?: FilterOp[];
+  /**
+   * Filters applied to what is already drawn behind the node, seen through its
+   * shape (rounded corners included) and faded by its opacity. A parent with
+   * its own layer (opacity, blend mode, clip, filter, mask) only shows what
+   * was drawn inside it.
+   */
+  backdropFilter?: FilterOp[];
+  /**
+   * Name of a LUT from the config's `luts`, graded onto the node's layer after
+   * its filters. Set it on the root to grade the whole frame.
+   */
+  lut?: string;
+  /** How much of the graded color to use, 0-1 (default 1). */
+  lutIntensity?: number;
+  lutInterpolation?: "tetrahedral" | "trilinear";
   
   // Shadows
   shadowColor?: string;
+  /** Blur radius in pixels, as in CSS `box-shadow` (σ = shadowBlur / 2). */
   shadowBlur?: number;
   shadowOffsetX?: number;
   shadowOffsetY?: number;
+  /** Grows (or with a negative value, shrinks) the shadow shape before blurring. */
+  shadowSpread?: number;
+  /** Draws the shadow inside the node's shape instead of behind it. */
+  shadowInset?: boolean;
 
-  // Text
+  // Text (inherited from the nearest ancestor that sets them)
   color?: string;
   fontSize?: number;
   fontFamily?: string;
@@ -79,23 +165,78 @@ export interface StyleConfig {
   // Image
   objectFit?: "fill" | "cover" | "contain";
 
-  // Transforms
+  // Transforms (applied as translate, rotate, skew, scale, matrix; children inherit them)
   rotate?: number;
   scale?: number;
+  scaleX?: number;
+  scaleY?: number;
   skewX?: number;
   skewY?: number;
+  translateX?: number;
+  translateY?: number;
+  /** CSS-style [a, b, c, d, e, f]. */
+  matrix?: [number, number, number, number, number, number];
+  /** Keywords, percentages or pixels, e.g. "top left", "50% 100%", "10px 20px". Defaults to the center. */
+  transformOrigin?: string;
+
+  // 3D (degrees; rotateX then rotateY, around the transform origin)
+  rotateX?: number;
+  rotateY?: number;
+  /** Viewing distance in pixels. Applies to the node itself and to its 3D-rotated children. */
+  perspective?: number;
+  /** "hidden" skips the node while its back faces the viewer. */
+  backfaceVisibility?: "visible" | "hidden";
 }
 
 export interface SceneNode {
   tag: "view" | "text" | "image" | "circle" | "rect" | "path" | "ellipse";
+  /** Names the node for patches to an uploaded scene. */
+  id?: string;
   text?: string;
   src?: string;
   d?: string;
-  style: StyleConfig;
+  // Vector paint; falls back to backgroundColor/backgroundGradient and borderColor/borderWidth.
+  fill?: Paint;
+  stroke?: Paint;
+  strokeWidth?: number;
+  fillRule?: "nonzero" | "evenodd";
+  fillOpacity?: number;
+  strokeOpacity?: number;
+  style?: StyleConfig;
+  /** Class names, applied in order before `style`: "card title" or ["card", "title"]. */
+  class?: string | string[];
+  /** Classes for the whole scene, overriding registered ones. Only read on the root node. */
+  styles?: Record<string, StyleConfig>;
   children?: SceneNode[];
   mask?: SceneNode; 
 }
 
+/** A node's computed box, from `computeLayout`. */
+export interface LayoutBox {
+  id: string | null;
+  tag: SceneNode["tag"];
+  /** Index of the parent's box in the list; null for the root. */
+  parent: number | null;
+  /** Layout box in canvas pixels, before transforms. */
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  /** `[a, b, c, d, e, f]` mapping node-local points to the canvas: `(a*x + c*y + e, b*x + d*y + f)`. 3D rotations are not included. */
+  transform: [number, number, number, number, number, number];
+  /** Text lines as drawn, in node-local pixels. */
+  lines?: { text: string; x: number; y: number; width: number; height: number }[];
+}
+
+/** An edit to a scene uploaded with `upload_scene`, sent through `patch_scene`. */
+export type ScenePatch =
+  /** Merges `style` into the node's style. */
+  | { op: "setStyle"; id: string; style: StyleConfig }
+  | { op: "setText"; id: string; text: string }
+  /** Inserts `node` at `index` among the parent's children, or last. */
+  | { op: "insertChild"; parent: string; index?: number; node: SceneNode }
+  | { op: "removeChild"; id: string };
+
 export interface RenderConfig {
   width: number;
   height: number;
@@ -104,6 +245,10 @@ export interface RenderConfig {
   assets?: Record<string, string>;
   videos?: Record<string, string>;
   fonts?: Record<string, string>;
+  /** `.cube` LUT files (1D or 3D) by name, for the `lut` style. */
+  luts?: Record<string, string>;
+  /** Classes registered once with the engine and usable by every frame's scene. */
+  styles?: Record<string, StyleConfig>;
   audio?: string;
   
   motionBlurSamples?: number;
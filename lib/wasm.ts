@@ -5,6 +5,7 @@ import type { RenderConfig } from "./types.js";
 
 let engineInstance: any = null;
 let wasmInitialized = false;
+let wasmMemory: WebAssembly.Memory | null = null;
 
 /**
  * Initializes the Wasm core and loads assets.
@@ -18,7 +19,8 @@ export async function getEngine(config: RenderConfig) {
       
       // Fix: Use the single-object parameter to avoid deprecation warnings
       // which mangle the terminal progress bar.
-      await init({ module_or_path: wasmBuffer });
+      const wasm = await init({ module_or_path: wasmBuffer });
+      wasmMemory = wasm.memory;
       
       wasmInitialized = true;
     } catch (e) {
@@ -38,6 +40,10 @@ export async function getEngine(config: RenderConfig) {
       }
     }
     
+    if (config.styles) {
+      engineInstance.register_styles(JSON.stringify(config.styles));
+    }
+
     // Load Static Assets
     if (config.assets) {
       for (const [id, path] of Object.entries(config.assets)) {
@@ -45,6 +51,14 @@ export async function getEngine(config: RenderConfig) {
         engineInstance.load_asset(id, new Uint8Array(buffer));
       }
     }
+
+    // Color grades (.cube)
+    if (config.luts) {
+      for (const [name, path] of Object.entries(config.luts)) {
+        const buffer = await readFile(path);
+        engineInstance.load_lut(name, new Uint8Array(buffer));
+      }
+    }
   }
   return engineInstance;
 }
@@ -52,3 +66,14 @@ export async function getEngine(config: RenderConfig) {
 export function getRawEngine() {
   return engineInstance;
 }
+
+/**
+ * The pixels of the engine's last `*_into_frame` render, viewed in place in wasm
+ * memory. The view is only valid until the next call into the engine: the next
+ * render overwrites it, and any call that grows wasm memory detaches it (it
+ * reads as empty). Copy it (`slice()`) to keep a frame longer, e.g. to transfer
+ * it to another thread.
+ */
+export function frameView(engine: any): Uint8Array {
+  return new Uint8Array(wasmMemory!.buffer, engine.frame_ptr(), engine.frame_len());
+}
@@ -1,4 +1,4 @@
-import { renderSingleFrame } from "./renderer.js";
+import { renderFrameView } from "./renderer.js";
 import type { RenderConfig } from "./types.js";
 
 declare var self: Worker;
@@ -6,7 +6,14 @@ declare var self: Worker;
 /**
  * Worker thread for rendering a chunk of frames.
  */
+/** Frame buffers the main thread has handed back, reused instead of allocating one per frame. */
+const pool: ArrayBuffer[] = [];
+
 self.onmessage = async (event: MessageEvent) => {
+  if (event.data.type === "recycle") {
+    pool.push(event.data.pixels);
+    return;
+  }
   const { startFrame, endFrame, config, props, componentPath } = event.data;
 
   try {
@@ -16,7 +23,13 @@ self.onmessage = async (event: MessageEvent) => {
     const sceneComponent = module.default || module[Object.keys(module)[0]];
 
     for (let i = startFrame; i <= endFrame; i++) {
-      const pixels = await renderSingleFrame(sceneComponent, config, i, props);
+      // Let buffers the main thread has handed back arrive before the pool is needed.
+      if (pool.length === 0) await new Promise(resolve => setTimeout(resolve, 0));
+      // The frame is a view on the engine's buffer; copy it into a pooled one that can be transferred.
+      const frame = await renderFrameView(sceneComponent, config, i, props);
+      const pooled = pool.pop();
+      const pixels = pooled?.byteLength === frame.length ? new Uint8Array(pooled) : new Uint8Array(frame.length);
+      pixels.set(frame);
       
       // Transfer the buffer back to the main thread (zero-copy)
       // Casting pixels.buffer to any solves the ArrayBuffer/Transferable type collision in Bun/TS
@@ -18,7 +18,7 @@ function GlassDemo() {
       justifyContent: "center", alignItems: "center"
     },
     children: [
-      // 1. Background Image (Blurred)
+      // 1. Background Image
       {
         tag: "image",
         src: "bg",
@@ -26,16 +26,20 @@ function GlassDemo() {
           width: 1280, height: 720,
           position: "absolute",
           objectFit: "cover",
-          blur: Math.abs(blurVal), // Dynamic Blur!
           brightness: 0.7
         }
       },
-      // 2. Foreground Card
+      // 2. Foreground Card, frosting whatever is behind it
       {
         tag: "view",
         style: {
           width: 600, height: 300,
           backgroundColor: "#ffffff22", // Semi-transparent
+          backdropFilter: [
+            { type: "blur", radius: Math.abs(blurVal) }, // Dynamic Blur!
+            { type: "saturate", amount: 1.4 },
+            { type: "brightness", amount: 1.1 }
+          ],
           borderColor: "#ffffff66",
           borderWidth: 2,
           borderRadius: 20,
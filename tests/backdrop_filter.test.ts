@@ -0,0 +1,66 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { FilterOp, SceneNode, StyleConfig } from "../lib/types.js";
+
+const SIZE = 40;
+
+// Left half white, right half black, with a rounded 20x20 card in the middle.
+const scene = (card: StyleConfig): SceneNode => ({
+  tag: "view",
+  style: { width: SIZE, height: SIZE, backgroundColor: "#000000" },
+  children: [
+    { tag: "view", style: { position: "absolute", left: 0, top: 0, width: 20, height: SIZE, backgroundColor: "#ffffff" } },
+    { tag: "view", style: { position: "absolute", left: 10, top: 10, width: 20, height: 20, borderRadius: 8, ...card } },
+  ],
+});
+
+const render = async (card: StyleConfig) => {
+  const engine = await getEngine({ width: SIZE, height: SIZE, fps: 30, duration: 1 });
+  const pixels = engine.render(JSON.stringify(scene(card)), SIZE, SIZE);
+  return (x: number, y: number) => pixels[(y * SIZE + x) * 4];
+};
+
+const blur: FilterOp[] = [{ type: "blur", radius: 3 }];
+
+describe("backdropFilter", () => {
+  test("blurs what lies under the node", async () => {
+    const red = await render({ backdropFilter: blur });
+    // The hard edge at x = 20 is smeared inside the card...
+    expect(red(18, 20)).toBeLessThan(255);
+    expect(red(18, 20)).toBeGreaterThan(128);
+    expect(red(21, 20)).toBeGreaterThan(0);
+    expect(red(21, 20)).toBeLessThan(128);
+    // ...and left alone outside it.
+    expect(red(18, 5)).toBe(255);
+    expect(red(21, 5)).toBe(0);
+  });
+
+  test("is clipped to rounded corners", async () => {
+    const red = await render({ backdropFilter: [{ type: "invert", amount: 1 }] });
+    // Corner pixels of the card's box lie outside the curve and keep the backdrop.
+    expect(red(10, 10)).toBe(255);
+    expect(red(29, 10)).toBe(0);
+    expect(red(10, 29)).toBe(255);
+    // Just inside the curve it is inverted.
+    expect(red(14, 14)).toBe(0);
+    expect(red(25, 14)).toBe(255);
+  });
+
+  test("fades with the node's opacity", async () => {
+    const full = await render({ backdropFilter: blur });
+    const half = await render({ backdropFilter: blur, opacity: 0.5 });
+    // Half way between the sharp and the blurred backdrop.
+    expect(Math.abs(half(21, 20) - full(21, 20) / 2)).toBeLessThanOrEqual(2);
+  });
+
+  test("applies color filters", async () => {
+    const red = await render({ backdropFilter: [{ type: "brightness", amount: 0.5 }] });
+    expect(red(15, 20)).toBe(127);
+    expect(red(5, 20)).toBe(255);
+  });
+
+  test("draws below the node's own fill", async () => {
+    const red = await render({ backdropFilter: blur, backgroundColor: "#000000" });
+    expect(red(18, 20)).toBe(0);
+  });
+});
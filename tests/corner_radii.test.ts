@@ -0,0 +1,45 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, x: number, y: number) => pixels[(y * 100 + x) * 4 + 3];
+
+describe("Corner Radii", () => {
+  test("each corner uses its own radius", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "view", style: { width: 100, height: 100, backgroundColor: "#ff0000", borderTopLeftRadius: 40, borderBottomRightRadius: 0 } };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    expect(alphaAt(pixels, 2, 2)).toBe(0);
+    expect(alphaAt(pixels, 97, 2)).toBe(255);
+    expect(alphaAt(pixels, 97, 97)).toBe(255);
+  });
+
+  test("per-corner radii override borderRadius", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "view", style: { width: 100, height: 100, backgroundColor: "#ff0000", borderRadius: 30, borderTopRightRadius: 0 } };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    expect(alphaAt(pixels, 2, 2)).toBe(0);
+    expect(alphaAt(pixels, 98, 1)).toBe(255);
+  });
+
+  test("large radii form a true circle", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "view", style: { width: 100, height: 100, backgroundColor: "#ff0000", borderRadius: 50 } };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    // 45° from the center, just inside and just outside radius 50.
+    expect(alphaAt(pixels, 50 + 34, 50 + 34)).toBe(255);
+    expect(alphaAt(pixels, 50 + 37, 50 + 37)).toBe(0);
+  });
+
+  test("children clip to asymmetric corners", async () => {
+    const engine = await getEngine(config);
+    const scene = {
+      tag: "view", style: { width: 100, height: 100, borderTopLeftRadius: 50 },
+      children: [{ tag: "rect", style: { width: 100, height: 100, backgroundColor: "#00ff00" } }],
+    };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    expect(alphaAt(pixels, 5, 5)).toBe(0);
+    expect(alphaAt(pixels, 95, 5)).toBe(255);
+  });
+});
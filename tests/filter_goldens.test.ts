@@ -0,0 +1,77 @@
+import { describe, expect, test } from "bun:test";
+import { readdirSync, readFileSync } from "node:fs";
+import path from "node:path";
+import { getEngine } from "../lib/wasm.js";
+
+// Each `<name>.json` scene in tests/golden has a `<name>.rgba` frame rendered
+// by the native CLI (`bun run golden:update`). The wasm build should match it
+// to within rounding.
+const GOLDEN_DIR = path.join(import.meta.dir, "golden");
+const SIZE = 64;
+const TOLERANCE = 3;
+
+const scenes = readdirSync(GOLDEN_DIR).filter((f) => f.endsWith(".json")).map((f) => f.slice(0, -".json".length));
+
+describe("Filter goldens", () => {
+  test("every scene has a golden frame", () => {
+    expect(scenes.length).toBeGreaterThan(0);
+    for (const name of scenes) {
+      expect(readFileSync(path.join(GOLDEN_DIR, `${name}.rgba`)).length).toBe(SIZE * SIZE * 4);
+    }
+  });
+
+  for (const name of scenes) {
+    test(name, async () => {
+      const engine = await getEngine({ width: SIZE, height: SIZE, fps: 30, duration: 1 });
+      const scene = readFileSync(path.join(GOLDEN_DIR, `${name}.json`), "utf8");
+      const golden = readFileSync(path.join(GOLDEN_DIR, `${name}.rgba`));
+      const pixels = engine.render(scene, SIZE, SIZE);
+
+      let worst = 0;
+      for (let i = 0; i < golden.length; i++) worst = Math.max(worst, Math.abs(pixels[i] - golden[i]));
+      expect(worst).toBeLessThanOrEqual(TOLERANCE);
+    });
+  }
+});
+
+// Tiny scenes whose filtered pixels can be worked out by hand, as a check on
+// the goldens that does not come from the renderer itself.
+describe("Hand-computed references", () => {
+  const row = (pixels: Uint8Array) => Array.from({ length: pixels.length / 4 }, (_, i) => Array.from(pixels.slice(i * 4, i * 4 + 4)));
+  const strip = (width: number, height: number, direction: string, filter: object, colors: [string, number][]) => ({
+    tag: "view",
+    style: { width, height, flexDirection: direction, filter: [filter] },
+    children: colors.map(([backgroundColor, size]) => ({
+      tag: "view",
+      style: direction === "row" ? { width: size, height: 1, backgroundColor } : { width: 1, height: size, backgroundColor },
+    })),
+  });
+
+  test("chromaticAberration between pixels", async () => {
+    const engine = await getEngine({ width: 6, height: 1, fps: 30, duration: 1 });
+    const scene = strip(6, 1, "row", { type: "chromaticAberration", offsetX: 1.5, offsetY: 0 }, [["#ffffff", 1], ["#000000", 4], ["#ffffff", 1]]);
+    // Red is read 1.5px to the left and blue 1.5px to the right, each the
+    // mean of the two pixels there (transparent past the edges); green stays.
+    expect(row(engine.render(JSON.stringify(scene), 6, 1))).toEqual([
+      [0, 255, 0, 255],
+      [128, 0, 0, 255], // red: (nothing + white) / 2
+      [128, 0, 0, 255], // red: (white + black) / 2
+      [0, 0, 128, 255], // blue: (black + white) / 2
+      [0, 0, 128, 255], // blue: (white + nothing) / 2
+      [0, 255, 0, 255],
+    ]);
+  });
+
+  test("chromaticAberration along y", async () => {
+    const engine = await getEngine({ width: 1, height: 4, fps: 30, duration: 1 });
+    const scene = strip(1, 4, "column", { type: "chromaticAberration", offsetX: 0, offsetY: 1 }, [["#ff00ff", 1], ["#00ff00", 2], ["#ff00ff", 1]]);
+    // Red comes from the pixel above, blue from the one below; alpha is the
+    // most opaque of the three reads.
+    expect(row(engine.render(JSON.stringify(scene), 1, 4))).toEqual([
+      [0, 0, 0, 255],
+      [255, 255, 0, 255],
+      [0, 255, 255, 255],
+      [0, 0, 0, 255],
+    ]);
+  });
+});
@@ -1,6 +1,7 @@
 import { describe, expect, test } from "bun:test";
 import { renderSingleFrame } from "../lib/engine.js";
-import type { SceneNode } from "../lib/types.js";
+import { getEngine } from "../lib/wasm.js";
+import type { FilterOp, SceneNode } from "../lib/types.js";
 
 const FilterScene = (): SceneNode => ({
   tag: "view",
@@ -30,3 +31,122 @@ describe("Filter System", () => {
     expect(buffer.length).toBe(100 * 100 * 4);
   });
 });
+
+const swatch = (style: Record<string, unknown>): SceneNode => ({
+  tag: "view",
+  style: { width: 40, height: 40 },
+  children: [{ tag: "view", style: { width: 20, height: 20, backgroundColor: "#ff0000", ...style } }],
+});
+
+const pixel = async (scene: SceneNode, x = 10, y = 10) => {
+  const engine = await getEngine({ width: 40, height: 40, fps: 30, duration: 1 });
+  const pixels = engine.render(JSON.stringify(scene), 40, 40);
+  const i = (y * 40 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+describe("Filter pipeline", () => {
+  test("color shorthands apply without blur", async () => {
+    const [r, g, b] = await pixel(swatch({ grayscale: 1 }));
+    expect(r).toBe(g);
+    expect(g).toBe(b);
+    expect(await pixel(swatch({ invert: 1 }))).toEqual([0, 255, 255, 255]);
+  });
+
+  test("filter runs in the listed order", async () => {
+    const graySepia = await pixel(swatch({ filter: [{ type: "saturate", amount: 0 }, { type: "sepia", amount: 1 }] satisfies FilterOp[] }));
+    const sepiaGray = await pixel(swatch({ filter: [{ type: "sepia", amount: 1 }, { type: "saturate", amount: 0 }] satisfies FilterOp[] }));
+    // Sepia last leaves a warm tint; desaturating last leaves gray.
+    expect(graySepia[0]).toBeGreaterThan(graySepia[2]);
+    expect(sepiaGray[0]).toBe(sepiaGray[2]);
+  });
+
+  test("shorthands run before the list", async () => {
+    expect(await pixel(swatch({ invert: 1, filter: [{ type: "grayscale", amount: 1 }] })))
+      .toEqual(await pixel(swatch({ filter: [{ type: "invert", amount: 1 }, { type: "grayscale", amount: 1 }] })));
+  });
+
+  test("each filter clamps its output before the next one", async () => {
+    // Brightened red saturates at 1, which contrast then pulls back to 0.75.
+    const filter = [{ type: "brightness", amount: 2 }, { type: "contrast", amount: 0.5 }] satisfies FilterOp[];
+    expect(await pixel(swatch({ filter }))).toEqual([191, 63, 63, 255]);
+  });
+
+  test("blur in the list spreads the layer", async () => {
+    const [, , , alpha] = await pixel(swatch({ filter: [{ type: "blur", radius: 3 }] }), 10, 22);
+    expect(alpha).toBeGreaterThan(0);
+    expect(alpha).toBeLessThan(255);
+  });
+
+  test("hueRotate turns colors around the wheel", async () => {
+    const [r, g, b] = await pixel(swatch({ filter: [{ type: "hueRotate", angle: 120 }] }));
+    expect(g).toBeGreaterThan(r);
+    expect(g).toBeGreaterThan(b);
+    const [full] = await pixel(swatch({ filter: [{ type: "hueRotate", angle: 360 }] }));
+    expect(full).toBeGreaterThanOrEqual(254);
+  });
+
+  test("filmGrain is the same for the same seed and frame", async () => {
+    const engine = await getEngine({ width: 40, height: 40, fps: 30, duration: 1 });
+    const grain = (seed: number, frame: number) =>
+      Array.from(engine.render(JSON.stringify(swatch({ filter: [{ type: "filmGrain", amount: 0.5, seed, frame }] })), 40, 40));
+    expect(grain(1, 2)).toEqual(grain(1, 2));
+    expect(grain(1, 3)).not.toEqual(grain(1, 2));
+    expect(grain(2, 2)).not.toEqual(grain(1, 2));
+  });
+
+  test("chromaticAberration moves red and blue apart", async () => {
+    const shifted = swatch({ filter: [{ type: "chromaticAberration", offsetX: 3, offsetY: 0 }] });
+    // Red reaches past the node's right edge; its left edge keeps only green and blue.
+    expect(await pixel(shifted, 21, 10)).toEqual([255, 0, 0, 255]);
+    expect(await pixel(shifted, 1, 10)).toEqual([0, 0, 0, 255]);
+  });
+
+  test("chromaticAberration offsets far past the canvas move the channels out of view", async () => {
+    const shifted = swatch({ filter: [{ type: "chromaticAberration", offsetX: 1e9, offsetY: 0 }] });
+    expect(await pixel(shifted, 10, 10)).toEqual([0, 0, 0, 255]);
+    expect(await pixel(shifted, 30, 10)).toEqual([0, 0, 0, 0]);
+  });
+
+  test("rejects unknown filter types", async () => {
+    const engine = await getEngine({ width: 40, height: 40, fps: 30, duration: 1 });
+    expect(() => engine.render(JSON.stringify(swatch({ filter: [{ type: "glow", amount: 1 }] })), 40, 40)).toThrow("unknown variant `glow`");
+  });
+});
+
+describe("Blur", () => {
+  const frame = async (scene: SceneNode) => {
+    const engine = await getEngine({ width: 40, height: 40, fps: 30, duration: 1 });
+    return engine.render(JSON.stringify(scene), 40, 40);
+  };
+  const centred = (style: Record<string, unknown>): SceneNode => ({
+    tag: "view",
+    style: { width: 40, height: 40 },
+    children: [{ tag: "view", style: { position: "absolute", left: 10, top: 10, width: 20, height: 20, backgroundColor: "#ff0000", ...style } }],
+  });
+
+  test("spreads evenly in every direction", async () => {
+    const pixels = await frame(centred({ blur: 4 }));
+    const alpha = (x: number, y: number) => pixels[(y * 40 + x) * 4 + 3];
+    for (const d of [1, 4, 8]) {
+      expect(alpha(10 - d, 20)).toBe(alpha(29 + d, 20));
+      expect(alpha(20, 10 - d)).toBe(alpha(20, 29 + d));
+      // The axes are blurred one after the other, so rounding can differ by one.
+      expect(Math.abs(alpha(10 - d, 20) - alpha(20, 10 - d))).toBeLessThanOrEqual(1);
+    }
+    expect(alpha(0, 0)).toBe(0);
+  });
+
+  test("keeps pixels premultiplied", async () => {
+    const pixels = await frame(centred({ backgroundColor: "#ffffff80", blur: 2.5 }));
+    for (let i = 0; i < pixels.length; i += 4) {
+      expect(Math.max(pixels[i], pixels[i + 1], pixels[i + 2])).toBeLessThanOrEqual(pixels[i + 3]);
+    }
+  });
+
+  test("sees content past the canvas edge", async () => {
+    // The square runs off the top; at row 0 the blur still has its full height to draw on.
+    const pixels = await frame(centred({ top: -20, height: 40, blur: 3 }));
+    expect(pixels[(0 * 40 + 20) * 4 + 3]).toBe(255);
+  });
+});
@@ -0,0 +1,66 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const RED = [255, 0, 0, 255];
+const GREEN = [0, 255, 0, 255];
+const BLUE = [0, 0, 255, 255];
+const EMPTY = [0, 0, 0, 0];
+
+const box = (backgroundColor: string, style: StyleConfig = {}): SceneNode =>
+  ({ tag: "view", style: { width: 20, height: 20, backgroundColor, ...style } });
+
+const render = async (style: StyleConfig, children: SceneNode[]) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100, ...style }, children };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Flexbox", () => {
+  test("flexWrap moves overflowing items to the next line, gap spaces them", async () => {
+    const wide = { width: 40 };
+    const pixels = await render(
+      { flexWrap: "wrap", gap: 10, alignContent: "flexStart" },
+      [box("#ff0000", wide), box("#00ff00", wide), box("#0000ff", wide)],
+    );
+    expect(pixelAt(pixels, 5, 5)).toEqual(RED);
+    expect(pixelAt(pixels, 45, 10)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 55, 10)).toEqual(GREEN);
+    expect(pixelAt(pixels, 5, 25)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 5, 35)).toEqual(BLUE);
+  });
+
+  test("spaceEvenly puts equal space around every item", async () => {
+    const pixels = await render({ justifyContent: "spaceEvenly" }, [box("#ff0000"), box("#00ff00"), box("#0000ff")]);
+    expect(pixelAt(pixels, 5, 5)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 15, 5)).toEqual(RED);
+    expect(pixelAt(pixels, 45, 5)).toEqual(GREEN);
+    expect(pixelAt(pixels, 75, 5)).toEqual(BLUE);
+  });
+
+  test("rowReverse starts at the end, alignSelf overrides alignItems", async () => {
+    const pixels = await render(
+      { flexDirection: "rowReverse", alignItems: "center" },
+      [box("#ff0000"), box("#00ff00", { alignSelf: "flexEnd" })],
+    );
+    expect(pixelAt(pixels, 90, 50)).toEqual(RED);
+    expect(pixelAt(pixels, 70, 50)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 70, 90)).toEqual(GREEN);
+  });
+
+  test("minWidth stops shrinking, flexShrink 0 prevents it", async () => {
+    const pixels = await render({}, [
+      box("#00ff00", { width: 80, minWidth: 60 }),
+      box("#0000ff", { width: 80, flexShrink: 0 }),
+    ]);
+    expect(pixelAt(pixels, 58, 5)).toEqual(GREEN);
+    expect(pixelAt(pixels, 62, 5)).toEqual(BLUE);
+  });
+});
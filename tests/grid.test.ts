@@ -0,0 +1,57 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const RED = [255, 0, 0, 255];
+const GREEN = [0, 255, 0, 255];
+const BLUE = [0, 0, 255, 255];
+const EMPTY = [0, 0, 0, 0];
+
+// Items have no size of their own; grid cells stretch them.
+const cell = (backgroundColor: string, style: StyleConfig = {}): SceneNode =>
+  ({ tag: "view", style: { backgroundColor, ...style } });
+
+const render = async (style: StyleConfig, children: SceneNode[]) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100, display: "grid", ...style }, children };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Grid", () => {
+  test("templates mix fr, pixel and repeat tracks; items span lines", async () => {
+    const pixels = await render(
+      { gridTemplateColumns: "repeat(2, 1fr) 20px", gridTemplateRows: "30px 1fr" },
+      [cell("#ff0000"), cell("#00ff00", { gridColumn: "2 / span 2" }), cell("#0000ff", { gridRow: 2, gridColumn: "1 / -1" })],
+    );
+    expect(pixelAt(pixels, 39, 29)).toEqual(RED);
+    expect(pixelAt(pixels, 41, 29)).toEqual(GREEN);
+    expect(pixelAt(pixels, 99, 0)).toEqual(GREEN);
+    expect(pixelAt(pixels, 50, 31)).toEqual(BLUE);
+    expect(pixelAt(pixels, 99, 99)).toEqual(BLUE);
+  });
+
+  test("gap separates cells and percentage tracks use the container size", async () => {
+    const pixels = await render(
+      { gridTemplateColumns: "1fr 1fr", gridTemplateRows: "50% 50%", gap: 10 },
+      [cell("#ff0000"), cell("#00ff00"), cell("#0000ff")],
+    );
+    expect(pixelAt(pixels, 44, 10)).toEqual(RED);
+    expect(pixelAt(pixels, 46, 10)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 56, 10)).toEqual(GREEN);
+    expect(pixelAt(pixels, 10, 55)).toEqual(EMPTY);
+    expect(pixelAt(pixels, 10, 61)).toEqual(BLUE);
+  });
+
+  test("invalid track lists are rejected", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "view", style: { display: "grid", gridTemplateColumns: "1fr repeat(2" } };
+    expect(() => engine.render(JSON.stringify(scene), 100, 100)).toThrow(/unbalanced/);
+  });
+});
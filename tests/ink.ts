@@ -0,0 +1,20 @@
+/** Which pixels count as ink: by default, those more than 100 alpha. */
+export type InkMatch = (r: number, g: number, b: number, a: number) => boolean;
+
+/**
+ * Inclusive bounds of the inked pixels in a `width`-pixel-wide RGBA frame,
+ * with their size. Empty frames give a negative size.
+ */
+export function inkBounds(pixels: Uint8Array, width: number, match: InkMatch = (_r, _g, _b, a) => a > 100) {
+  const height = pixels.length / 4 / width;
+  let [left, top, right, bottom] = [width, height, -1, -1];
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const i = (y * width + x) * 4;
+      if (match(pixels[i], pixels[i + 1], pixels[i + 2], pixels[i + 3])) {
+        [left, top, right, bottom] = [Math.min(left, x), Math.min(top, y), Math.max(right, x), Math.max(bottom, y)];
+      }
+    }
+  }
+  return { left, top, right, bottom, width: right - left + 1, height: bottom - top + 1 };
+}
@@ -0,0 +1,79 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { getEngine } from "../lib/wasm.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const pixel = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+describe("Layer Bounds", () => {
+  test("layered nodes land where direct draws do", async () => {
+    const engine = await getEngine(config);
+    const child = (style: object) => ({
+      tag: "rect",
+      style: { position: "absolute", left: 37, top: 41, width: 20, height: 20, backgroundColor: "#ff0000", ...style },
+    });
+    const render = (style: object) => engine.render(JSON.stringify({
+      tag: "view", style: { width: 100, height: 100 }, children: [child(style)],
+    }), 100, 100);
+
+    const direct = render({});
+    const layered = render({ opacity: 0.999 });
+    for (const [x, y] of [[36, 40], [37, 41], [56, 60], [57, 61]]) {
+      expect(pixel(layered, x, y)[3] > 0).toBe(pixel(direct, x, y)[3] > 0);
+    }
+  });
+
+  test("direct and layered draws match byte for byte", async () => {
+    const engine = await getEngine(config);
+    engine.load_font("default", new Uint8Array(await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"))));
+    const paints = [
+      { tag: "rect", style: { backgroundColor: "#ff000080" } },
+      { tag: "rect", style: { backgroundColor: "#3366ff", rotate: 17 } },
+      { tag: "circle", style: { backgroundColor: "#00ff0040", borderWidth: 3, borderColor: "#ffffffa0" } },
+      { tag: "ellipse", style: { backgroundGradient: { type: "linear", colors: ["#ff000080", "#0000ffe0"], angle: 30 }, rotate: -8 } },
+      { tag: "rect", style: { backgroundColor: "#ffcc00c0", borderRadius: 6, shadowColor: "#00000080", shadowBlur: 5, shadowOffsetX: 4 } },
+      { tag: "rect", style: { backgroundColor: "#ffffff", shadowColor: "#ff0000", shadowBlur: 3, shadowInset: true, rotate: 5 } },
+      { tag: "path", d: "M 0 0 Q 30 40 40 0 Z", style: { backgroundColor: "#00ffff90", borderWidth: 2, borderColor: "#ff00ff" } },
+      { tag: "text", text: "Hi", style: { fontSize: 24, color: "#ffffffb0", rotate: 12 } },
+    ];
+    // A missing LUT forces a layer without changing any pixels.
+    const render = (paint: { style: object }, layered: boolean) => engine.render(JSON.stringify({
+      tag: "view",
+      style: { width: 100, height: 100, backgroundGradient: { type: "linear", colors: ["#20406080", "#e0c0a0"], angle: 60 } },
+      children: [{ ...paint, style: { position: "absolute", left: 30.5, top: 28.3, width: 40, height: 40, ...paint.style, ...(layered ? { lut: "missing" } : {}) } }],
+    }), 100, 100);
+
+    for (const paint of paints) {
+      expect(render(paint, false)).toEqual(render(paint, true));
+    }
+  });
+
+  test("child blend modes still blend inside their parent only", async () => {
+    const engine = await getEngine(config);
+    const scene = {
+      tag: "view", style: { width: 100, height: 100, backgroundColor: "#ffffff" },
+      children: [{
+        tag: "view", style: { width: 50, height: 50 },
+        children: [{ tag: "rect", style: { width: 50, height: 50, backgroundColor: "#ff0000", blendMode: "multiply" } }],
+      }],
+    };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    // Multiply against the (empty) parent group leaves plain red, not white * red.
+    expect(pixel(pixels, 10, 10)).toEqual([255, 0, 0, 255]);
+  });
+
+  test("clipped content outside the canvas does not throw", async () => {
+    const engine = await getEngine(config);
+    const scene = {
+      tag: "view", style: { width: 100, height: 100 },
+      children: [{ tag: "view", style: { position: "absolute", left: -500, top: -500, width: 50, height: 50, overflow: "hidden", backgroundColor: "#ff0000" } }],
+    };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    expect(pixel(pixels, 0, 0)[3]).toBe(0);
+  });
+});
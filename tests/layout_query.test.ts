@@ -0,0 +1,93 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "fs/promises";
+import { join } from "path";
+import { computeLayout } from "../lib/engine.js";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+import { inkBounds } from "./ink.js";
+
+const config = { width: 200, height: 100, fps: 30, duration: 1 };
+
+describe("Layout queries", () => {
+  test("returns canvas boxes in document order with parents", async () => {
+    const boxes = await computeLayout({
+      tag: "view",
+      id: "root",
+      style: { width: 200, height: 100, flexDirection: "row", padding: 10 },
+      children: [
+        { tag: "view", id: "a", style: { width: 20, height: 20 } },
+        { tag: "view", children: [{ tag: "rect", id: "deep", style: { width: 5, height: 5, marginLeft: 3 } }] },
+      ],
+    }, config);
+    expect(boxes.map(b => [b.id, b.parent, b.x, b.y, b.width, b.height])).toEqual([
+      ["root", null, 0, 0, 200, 100],
+      ["a", 0, 10, 10, 20, 20],
+      [null, 0, 30, 10, 8, 5],
+      ["deep", 2, 33, 10, 5, 5],
+    ]);
+    expect(boxes[3].transform).toEqual([1, 0, 0, 1, 33, 10]);
+  });
+
+  test("includes ancestor transforms", async () => {
+    const boxes = await computeLayout({
+      tag: "view",
+      style: { width: 200, height: 100 },
+      children: [{ tag: "view", id: "spun", style: { width: 20, height: 20, rotate: 90 } }],
+    }, config);
+    const [a, b, c, d, e, f] = boxes[1].transform;
+    // The top-left corner turns about the centre (10, 10) to land at (20, 0).
+    expect(a).toBeCloseTo(0);
+    expect(b).toBeCloseTo(1);
+    expect(c).toBeCloseTo(-1);
+    expect(d).toBeCloseTo(0);
+    expect(e).toBeCloseTo(20);
+    expect(f).toBeCloseTo(0);
+  });
+
+  test("measures text lines as drawn", async () => {
+    const engine = await getEngine(config);
+    const font = await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"));
+    engine.load_font("Roboto", new Uint8Array(font));
+    const scene: SceneNode = {
+      tag: "view",
+      style: { width: 200, height: 100 },
+      children: [{ tag: "text", id: "label", text: "hello wide world", style: { width: 60, fontFamily: "Roboto", fontSize: 20, textAlign: "center" } }],
+    };
+    const [, label] = await computeLayout(scene, config);
+    expect(label.lines!.map(l => l.text)).toEqual(["hello", "wide", "world"]);
+    expect(label.lines![1].y).toBe(24);
+    for (const line of label.lines!) expect(line.x).toBeCloseTo((60 - line.width) / 2);
+  });
+
+  test("line boxes of rotated text cover the drawn glyphs", async () => {
+    const engine = await getEngine(config);
+    engine.load_font("default", new Uint8Array(await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"))));
+    const scene: SceneNode = {
+      tag: "view",
+      style: { width: 100, height: 100 },
+      children: [{ tag: "text", id: "label", text: "HHHHHH", style: { position: "absolute", left: 20, top: 40, width: 60, height: 20, fontSize: 16, color: "#ffffff", rotate: 90 } }],
+    };
+    const [, label] = await computeLayout(scene, { ...config, width: 100 });
+    const [line] = label.lines!;
+    // The line box in canvas pixels, through the node's transform.
+    const [a, b, c, d, e, f] = label.transform;
+    const corners = [[line.x, line.y], [line.x + line.width, line.y], [line.x, line.y + line.height], [line.x + line.width, line.y + line.height]]
+      .map(([x, y]) => [a * x + c * y + e, b * x + d * y + f]);
+    const xs = corners.map(([x]) => x);
+    const ys = corners.map(([, y]) => y);
+    const box = { left: Math.min(...xs), right: Math.max(...xs), top: Math.min(...ys), bottom: Math.max(...ys) };
+    expect(box.bottom - box.top).toBeCloseTo(line.width);
+
+    const ink = inkBounds(engine.render(JSON.stringify(scene), 100, 100), 100, (_r, _g, _b, a) => a > 0);
+    expect(ink.left).toBeGreaterThanOrEqual(Math.floor(box.left));
+    expect(ink.right).toBeLessThan(Math.ceil(box.right));
+    expect(ink.top).toBeGreaterThanOrEqual(Math.floor(box.top));
+    expect(ink.bottom).toBeLessThan(Math.ceil(box.bottom));
+    // The glyphs run the length of the box, not across it.
+    expect(ink.bottom - ink.top).toBeGreaterThan((box.bottom - box.top) * 0.9);
+  });
+
+  test("rejects bad scenes like render", async () => {
+    await expect(computeLayout({ tag: "blob" as any }, config)).rejects.toThrow("Unknown node tag 'blob'");
+  });
+});
@@ -1,14 +1,7 @@
-import { describe, expect, test, mock } from "bun:test";
+import { describe, expect, test } from "bun:test";
 import { lottieToScene } from "../lib/lottie/index.js";
 import type { LottieJSON } from "../lib/lottie/types.js";
 
-mock.module("../lib/engine.js", () => {
-    return {
-        measurePath: () => 100, // Always return 100px length
-        getRawEngine: () => ({})
-    };
-});
-
 describe("Lottie Trim Path", () => {
   const mockLottie: LottieJSON = {
     fr: 30, ip: 0, op: 60, w: 100, h: 100,
@@ -22,14 +15,14 @@ describe("Lottie Trim Path", () => {
           // Stroke
           { ty: "st", c: { a: 0, k: [1, 0, 0] }, w: { a: 0, k: 5 } },
           // Trim Path: Start 10%, End 90%
-          { ty: "tm", s: { a: 0, k: 10 }, e: { a: 0, k: 90 }, o: { a: 0, k: 0 } },
+          { ty: "tm", s: { a: 0, k: 10 }, e: { a: 0, k: 90 }, o: { a: 0, k: 90 } },
           // Shape with explicit empty tangents to verify correct Lottie structure
           { ty: "sh", ks: { a: 0, k: { v: [[0,0],[10,10]], i:[[0,0],[0,0]], o:[[0,0],[0,0]], c: false } } }
       ]
     }]
   };
 
-  test("applies stroke dash array based on trim properties", () => {
+  test("passes trim properties through to the path", () => {
     const scene = lottieToScene(mockLottie, 0);
     const layer = scene.children?.[0];
     const shape = layer?.children?.[0];
@@ -37,13 +30,11 @@ describe("Lottie Trim Path", () => {
     expect(shape).toBeDefined();
     expect(shape?.tag).toBe("path");
     
-    // We mocked measurePath to return 100.
-    // Start 10% = 10. End 90% = 90.
-    // Visible = 80.
-    // Offset = -10.
-    
-    expect(shape?.style.strokeDashArray).toEqual([80, 100]);
-    expect(shape?.style.strokeDashOffset).toBe(-10);
+    // The engine trims natively; Lottie's 90° offset is a quarter of the path.
+    expect(shape?.style.trimStart).toBe(10);
+    expect(shape?.style.trimEnd).toBe(90);
+    expect(shape?.style.trimOffset).toBe(25);
+    expect(shape?.style.strokeDashArray).toBeUndefined();
     expect(shape?.style.borderWidth).toBe(5);
   });
 });
\ No newline at end of file
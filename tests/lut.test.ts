@@ -0,0 +1,107 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+
+type Grade = (r: number, g: number, b: number) => [number, number, number];
+
+/** A `.cube` 3D table of `size` entries per axis, red varying fastest. */
+function cube3d(size: number, grade: Grade): string {
+  const lines = [`TITLE "test"`, `LUT_3D_SIZE ${size}`];
+  for (let b = 0; b < size; b++) {
+    for (let g = 0; g < size; g++) {
+      for (let r = 0; r < size; r++) {
+        lines.push(grade(r / (size - 1), g / (size - 1), b / (size - 1)).map((v) => v.toFixed(6)).join(" "));
+      }
+    }
+  }
+  return lines.join("\n");
+}
+
+const encoder = new TextEncoder();
+
+const engine = async () => {
+  const engine = await getEngine({ width: 4, height: 1, fps: 30, duration: 1 });
+  engine.load_lut("invert", encoder.encode(cube3d(17, (r, g, b) => [1 - r, 1 - g, 1 - b])));
+  engine.load_lut("square", encoder.encode(cube3d(9, (r, g, b) => [r * r, g * g, b * b])));
+  engine.load_lut("rotate", encoder.encode(cube3d(2, (r, g, b) => [b, r, g])));
+  engine.load_lut("affine", encoder.encode(cube3d(3, (r, g, b) => [0.75 * r + 0.25 * g, 1 - b, 0.25 + 0.5 * b])));
+  engine.load_lut("sqrt-red", encoder.encode("# 1D\nLUT_1D_SIZE 5\n" + [0, 0.25, 0.5, 0.75, 1].map((v) => `${Math.sqrt(v)} ${v} ${v}`).join("\n")));
+  return engine;
+};
+
+// Red, mid gray, sky blue and half-transparent red, one pixel each.
+const swatches = (style: StyleConfig): SceneNode => ({
+  tag: "view",
+  style: { width: 4, height: 1, flexDirection: "row", ...style },
+  children: ["#ff0000", "#808080", "#40c0ff", "#ff000080"].map((backgroundColor) => ({
+    tag: "view",
+    style: { width: 1, height: 1, backgroundColor },
+  })),
+});
+
+const render = async (style: StyleConfig) => {
+  const pixels = (await engine()).render(JSON.stringify(swatches(style)), 4, 1);
+  return [0, 1, 2, 3].map((i) => Array.from(pixels.slice(i * 4, i * 4 + 4)));
+};
+
+describe("LUTs", () => {
+  test("grades the node's layer through a 3D table", async () => {
+    expect(await render({ lut: "invert" })).toEqual([
+      [0, 255, 255, 255],
+      [127, 127, 127, 255],
+      [191, 63, 0, 255],
+      // Graded un-premultiplied, so alpha is kept.
+      [0, 128, 128, 128],
+    ]);
+    const [, gray, sky] = await render({ lut: "square" });
+    expect(gray).toEqual([64, 64, 64, 255]);
+    expect(sky).toEqual([16, 145, 255, 255]);
+  });
+
+  test("matches hand-computed grades", async () => {
+    // Any interpolation reproduces an affine grade exactly, so each pixel is
+    // (0.75r + 0.25g, 1 - b, 0.25 + 0.5b) of its color, worked out by hand:
+    const expected = [
+      [191, 255, 64, 255], // red: 191.25, 255, 63.75
+      [128, 127, 128, 255], // #808080: 128, 255 - 128, 63.75 + 64
+      [96, 0, 191, 255], // #40c0ff: 48 + 48, 0, 63.75 + 127.5
+      [96, 128, 32, 128], // red at half alpha: the red grade times 128/255
+    ];
+    expect(await render({ lut: "affine" })).toEqual(expected);
+    expect(await render({ lut: "affine", lutInterpolation: "trilinear" })).toEqual(expected);
+  });
+
+  test("moves colors between channels", async () => {
+    const [red] = await render({ lut: "rotate" });
+    expect(red).toEqual([0, 255, 0, 255]);
+  });
+
+  test("both interpolations agree on the table's own entries", async () => {
+    expect(await render({ lut: "rotate", lutInterpolation: "trilinear" })).toEqual(await render({ lut: "rotate" }));
+  });
+
+  test("applies 1D tables per channel", async () => {
+    const [, gray] = await render({ lut: "sqrt-red" });
+    expect(gray).toEqual([181, 128, 128, 255]);
+  });
+
+  test("lutIntensity mixes with the original", async () => {
+    const [red] = await render({ lut: "invert", lutIntensity: 0.5 });
+    expect(red).toEqual([128, 128, 128, 255]);
+    expect(await render({ lut: "invert", lutIntensity: 0 })).toEqual(await render({}));
+  });
+
+  test("a missing LUT leaves the node alone and warns", async () => {
+    const e = await engine();
+    expect(await render({ lut: "nope" })).toEqual(await render({}));
+    e.render(JSON.stringify(swatches({ lut: "nope" })), 4, 1);
+    expect(JSON.parse(e.warnings())).toContainEqual({ kind: "missingLut", name: "nope" });
+  });
+
+  test("rejects malformed files", async () => {
+    const e = await engine();
+    expect(() => e.load_lut("bad", encoder.encode("LUT_3D_SIZE 2\n0 0 0\n1 1\n"))).toThrow("line 3: table entries need three numbers");
+    expect(() => e.load_lut("short", encoder.encode("LUT_3D_SIZE 2\n0 0 0\n"))).toThrow("expected 8 table entries, found 1");
+    expect(() => e.load_lut("sizeless", encoder.encode("0 0 0\n"))).toThrow("missing LUT_1D_SIZE or LUT_3D_SIZE");
+  });
+});
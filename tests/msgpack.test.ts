@@ -0,0 +1,45 @@
+import { describe, expect, test } from "bun:test";
+import { encodeScene } from "../lib/msgpack.js";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 120, height: 80, fps: 30, duration: 1 };
+
+describe("MessagePack scenes", () => {
+  test("encodes values in their smallest form", () => {
+    expect(Array.from(encodeScene({ a: 1, b: -1, c: 200, d: null }))).toEqual([
+      0x84, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xff, 0xa1, 0x63, 0xcc, 0xc8, 0xa1, 0x64, 0xc0,
+    ]);
+    expect(Array.from(encodeScene(0.5))).toEqual([0xcb, 0x3f, 0xe0, 0, 0, 0, 0, 0, 0]);
+    expect(Array.from(encodeScene("é"))).toEqual([0xa2, 0xc3, 0xa9]);
+    expect(encodeScene("x".repeat(40))[0]).toBe(0xd9);
+  });
+
+  test("drops what JSON.stringify drops", () => {
+    expect(encodeScene({ a: undefined, b: () => 1, c: [undefined], d: NaN }))
+      .toEqual(encodeScene(JSON.parse(JSON.stringify({ a: undefined, b: () => 1, c: [undefined], d: NaN }))));
+  });
+
+  test("render_binary matches render", async () => {
+    const engine = await getEngine(config);
+    const scene: SceneNode = {
+      tag: "view",
+      style: { width: 120, height: 80, display: "grid", gridTemplateColumns: "repeat(2, 1fr)", padding: "5%", backgroundColor: "#202020" },
+      styles: { dot: { width: "2em", height: 20, borderRadius: 10 } },
+      children: [
+        { tag: "view", class: "dot", style: { backgroundColor: "#ff0000", opacity: 0.5 } },
+        { tag: "circle", style: { width: 30, height: 30, gridColumn: "2", backgroundGradient: { colors: ["#00ff00", "#0000ff"], angle: 45 } } },
+        { tag: "rect", style: { width: "50vw", height: 10, rotate: 12.5, backgroundColor: "#ffffff" } },
+      ],
+    };
+    const binary = engine.render_binary(encodeScene(scene), 120, 80);
+    const json = engine.render(JSON.stringify(scene), 120, 80);
+    expect(binary).toEqual(json);
+  });
+
+  test("rejects data that is not a scene", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.render_binary(encodeScene({ style: {} }), 120, 80)).toThrow("Invalid binary scene");
+    expect(() => engine.render_binary(new Uint8Array([0xc1]), 120, 80)).toThrow("Invalid binary scene");
+  });
+});
@@ -0,0 +1,63 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { getEngine } from "../lib/wasm.js";
+import type { StyleConfig } from "../lib/types.js";
+import { inkBounds } from "./ink.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, x: number, y: number) => pixels[(y * 100 + x) * 4 + 3];
+
+const render = async (style: StyleConfig, parent: StyleConfig = {}) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100, ...parent }, children: [{ tag: "view", style }] };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+// A 40x40 red square at (30, 30).
+const square: StyleConfig = { position: "absolute", left: 30, top: 30, width: 40, height: 40, backgroundColor: "#ff0000" };
+
+describe("Perspective", () => {
+  test("rotateX without perspective foreshortens", async () => {
+    const pixels = await render({ ...square, rotateX: 60 });
+    expect(alphaAt(pixels, 50, 34)).toBe(0);
+    expect(alphaAt(pixels, 50, 40)).toBe(255);
+    expect(alphaAt(pixels, 32, 50)).toBe(255);
+  });
+
+  test("the far side shrinks under the parent's perspective", async () => {
+    const pixels = await render({ ...square, rotateY: 60 }, { perspective: 200 });
+    // The left edge swings toward the viewer and past the flat 40px mark.
+    expect(alphaAt(pixels, 40, 50)).toBe(255);
+    expect(alphaAt(pixels, 50, 34)).toBe(255);
+    expect(alphaAt(pixels, 60, 50)).toBe(0);
+  });
+
+  test("a node's own perspective turns around its transform origin", async () => {
+    const pixels = await render({ ...square, rotateY: 60, perspective: 100, transformOrigin: "left" });
+    expect(alphaAt(pixels, 31, 31)).toBe(255);
+    expect(alphaAt(pixels, 60, 50)).toBe(0);
+  });
+
+  test("hidden backfaces are culled", async () => {
+    const visible = await render({ ...square, rotateY: 180 });
+    const hidden = await render({ ...square, rotateY: 180, backfaceVisibility: "hidden" });
+    expect(alphaAt(visible, 50, 50)).toBe(255);
+    expect(alphaAt(hidden, 50, 50)).toBe(0);
+  });
+
+  test("projected text keeps its 2D rotation", async () => {
+    const engine = await getEngine(config);
+    engine.load_font("default", new Uint8Array(await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"))));
+    const inkBox = (style: StyleConfig) => {
+      const text = { tag: "text", text: "HHHHHH", style: { position: "absolute", left: 20, top: 40, width: 60, height: 20, fontSize: 16, color: "#ffffff", rotate: 90, ...style } };
+      return inkBounds(engine.render(JSON.stringify({ tag: "view", style: { width: 100, height: 100 }, children: [text] }), 100, 100), 100);
+    };
+    const flat = inkBox({});
+    const turned = inkBox({ rotateY: 40, perspective: 150 });
+    // The run still reads downwards, foreshortened along its length.
+    expect(turned.height).toBeGreaterThan(turned.width * 3);
+    expect(turned.height).toBeLessThan(flat.height - 10);
+  });
+});
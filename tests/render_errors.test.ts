@@ -0,0 +1,78 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+
+const config = { width: 10, height: 10, fps: 30, duration: 1 };
+
+describe("Render Errors", () => {
+  test("malformed JSON throws with line and column instead of aborting", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.render('{"tag": "view",\n "style": {', 10, 10)).toThrow(/line 2, column/);
+  });
+
+  test("unknown tags are rejected", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "view", style: {}, children: [{ tag: "sprite", style: {} }] };
+    expect(() => engine.render(JSON.stringify(scene), 10, 10)).toThrow(/Unknown node tag 'sprite'/);
+  });
+
+  test("zero-sized frames are rejected", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.render(JSON.stringify({ tag: "view", style: {} }), 0, 10)).toThrow(/Invalid render dimensions/);
+  });
+
+  test("zero-sized raw assets are rejected", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.load_asset_raw("empty", new Uint8Array(0), 0, 4)).toThrow(/Invalid render dimensions 0x4/);
+  });
+
+  test("huge blurs render, and layers too large to allocate throw", async () => {
+    const engine = await getEngine(config);
+    for (const blur of [5000, 1e9]) {
+      const scene = { tag: "view", style: { width: 10, height: 10 }, children: [{ tag: "view", style: { width: 4, height: 4, backgroundColor: "#ff0000", blur } }] };
+      expect(engine.render(JSON.stringify(scene), 10, 10).length).toBe(400);
+    }
+    const huge = { tag: "view", style: { position: "absolute", left: -50000, top: -50000, width: 100000, height: 100000, backgroundColor: "#ff0000", blur: 5000 } };
+    expect(() => engine.render(JSON.stringify({ tag: "view", style: {}, children: [huge] }), 10, 10)).toThrow(/Failed to allocate/);
+  });
+
+  test("the engine keeps rendering after an error", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.render("not json", 10, 10)).toThrow();
+    const pixels = engine.render(JSON.stringify({ tag: "view", style: { width: 10, height: 10, backgroundColor: "#ff0000" } }), 10, 10);
+    expect(pixels[0]).toBe(255);
+  });
+
+  test("paths without segments draw nothing but keep their children", async () => {
+    const engine = await getEngine(config);
+    for (const d of ["", "M 5 5", "not a path"]) {
+      const scene = {
+        tag: "view", style: { width: 10, height: 10 },
+        children: [{
+          tag: "path", d, style: { width: 10, height: 10, backgroundColor: "#ff0000", shadowColor: "#000000", shadowBlur: 2 },
+          children: [{ tag: "view", style: { width: 4, height: 4, backgroundColor: "#00ff00" } }],
+        }],
+      };
+      const pixels = engine.render(JSON.stringify(scene), 10, 10);
+      expect(Array.from(pixels.slice(0, 4))).toEqual([0, 255, 0, 255]);
+      expect(Array.from(pixels.slice((8 * 10 + 8) * 4, (8 * 10 + 8) * 4 + 4))).toEqual([0, 0, 0, 0]);
+    }
+  });
+
+  test("gradients with fewer stops than colors space the rest evenly", async () => {
+    const engine = await getEngine(config);
+    const render = (stops: number[]) => engine.render(JSON.stringify({
+      tag: "view",
+      style: { width: 10, height: 10, backgroundGradient: { type: "linear", angle: 90, colors: ["#ff0000", "#00ff00", "#0000ff"], stops } },
+    }), 10, 10);
+    const full = render([0, 0.5, 1]);
+    expect(render([0, 0.5])).toEqual(full);
+    expect(render([])).toEqual(full);
+  });
+
+  test("missing assets are reported as warnings", async () => {
+    const engine = await getEngine(config);
+    const scene = { tag: "image", src: "nope", style: { width: 10, height: 10 } };
+    engine.render(JSON.stringify(scene), 10, 10);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingAsset", src: "nope" }]);
+  });
+});
@@ -0,0 +1,65 @@
+import { describe, expect, test } from "bun:test";
+import { encodeScene } from "../lib/msgpack.js";
+import { frameView, getEngine } from "../lib/wasm.js";
+import { renderFrameView, renderSingleFrame } from "../lib/renderer.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 100, height: 60, fps: 30, duration: 1 };
+
+const scene = (color: string): SceneNode => ({
+  tag: "view",
+  style: { width: 100, height: 60, backgroundColor: "#000000" },
+  children: [
+    // Layered children exercise the pooled pixmaps.
+    { tag: "view", style: { width: 40, height: 40, backgroundColor: color, opacity: 0.5, borderRadius: 8 } },
+    { tag: "circle", style: { width: 30, height: 30, backgroundColor: color, blendMode: "screen", shadowColor: "#ffffff", shadowBlur: 4 } },
+  ],
+});
+
+describe("Rendering into the frame buffer", () => {
+  test("matches render", async () => {
+    const engine = await getEngine(config);
+    engine.render_into_frame(encodeScene(scene("#ff0000")), 100, 60);
+    expect(frameView(engine)).toEqual(engine.render(JSON.stringify(scene("#ff0000")), 100, 60));
+  });
+
+  test("JSON and retained scenes render into the frame buffer too", async () => {
+    const engine = await getEngine(config);
+    const json = JSON.stringify(scene("#00ffff"));
+    const expected = engine.render(json, 100, 60);
+    engine.render_json_into_frame(json, 100, 60);
+    expect(frameView(engine)).toEqual(expected);
+    engine.upload_scene(json);
+    engine.render_retained_into_frame(100, 60);
+    expect(frameView(engine)).toEqual(expected);
+  });
+
+  test("renderSingleFrame results outlive the next render, views do not", async () => {
+    const color = (c: string) => () => scene(c);
+    const kept = await renderSingleFrame(color("#ff0000"), config, 0, {});
+    const view = await renderFrameView(color("#ff0000"), config, 0, {});
+    await renderSingleFrame(color("#0000ff"), config, 0, {});
+    const at = (10 * 100 + 10) * 4;
+    expect(Array.from(kept.slice(at, at + 4))).toEqual([128, 0, 0, 255]);
+    expect(Array.from(view.slice(at, at + 4))).toEqual([0, 0, 128, 255]);
+  });
+
+  test("reuses the buffer and clears it between frames", async () => {
+    const engine = await getEngine(config);
+    engine.render_into_frame(encodeScene(scene("#ff0000")), 100, 60);
+    const ptr = engine.frame_ptr();
+    engine.render_into_frame(encodeScene({ tag: "view", style: { width: 10, height: 10, backgroundColor: "#00ff00" } }), 100, 60);
+    expect(engine.frame_ptr()).toBe(ptr);
+    const view = frameView(engine);
+    expect(view.length).toBe(100 * 60 * 4);
+    expect(Array.from(view.slice(0, 4))).toEqual([0, 255, 0, 255]);
+    expect(Array.from(view.slice((30 * 100 + 30) * 4, (30 * 100 + 30) * 4 + 4))).toEqual([0, 0, 0, 0]);
+  });
+
+  test("reports errors and warnings like render", async () => {
+    const engine = await getEngine(config);
+    expect(() => engine.render_into_frame(encodeScene(scene("#ff0000")), 0, 60)).toThrow("Invalid render dimensions 0x60");
+    engine.render_into_frame(encodeScene({ tag: "image", src: "missing" }), 100, 60);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingAsset", src: "missing" }]);
+  });
+});
@@ -0,0 +1,113 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "fs/promises";
+import { join } from "path";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, ScenePatch } from "../lib/types.js";
+
+const config = { width: 200, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 200 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const scene: SceneNode = {
+  tag: "view",
+  id: "root",
+  style: { width: 200, height: 100, flexDirection: "row" },
+  children: [
+    { tag: "view", id: "a", style: { width: 20, height: 20, backgroundColor: "#ff0000" } },
+    { tag: "view", id: "b", style: { width: 20, height: 20, backgroundColor: "#0000ff" } },
+  ],
+};
+
+const upload = async () => {
+  const engine = await getEngine(config);
+  engine.upload_scene(JSON.stringify(scene));
+  return engine;
+};
+
+const patch = (engine: any, patches: ScenePatch | ScenePatch[]) => engine.patch_scene(JSON.stringify(patches));
+
+describe("Retained scenes", () => {
+  test("renders like the same scene sent whole", async () => {
+    const engine = await upload();
+    expect(engine.render_retained(200, 100)).toEqual(engine.render(JSON.stringify(scene), 200, 100));
+  });
+
+  test("setStyle merges into the node and moves its siblings", async () => {
+    const engine = await upload();
+    engine.render_retained(200, 100);
+    patch(engine, { op: "setStyle", id: "a", style: { width: 50 } });
+    const pixels = engine.render_retained(200, 100);
+    expect(pixelAt(pixels, 45, 5)).toEqual([255, 0, 0, 255]);
+    expect(pixelAt(pixels, 55, 5)).toEqual([0, 0, 255, 255]);
+  });
+
+  test("insertChild and removeChild update layout", async () => {
+    const engine = await upload();
+    engine.render_retained(200, 100);
+    patch(engine, [
+      { op: "insertChild", parent: "root", index: 0, node: { tag: "view", id: "c", style: { width: 10, height: 20, backgroundColor: "#00ff00" } } },
+      { op: "removeChild", id: "a" },
+    ]);
+    const pixels = engine.render_retained(200, 100);
+    expect(pixelAt(pixels, 5, 5)).toEqual([0, 255, 0, 255]);
+    expect(pixelAt(pixels, 15, 5)).toEqual([0, 0, 255, 255]);
+    expect(pixelAt(pixels, 35, 5)).toEqual([0, 0, 0, 0]);
+  });
+
+  test("setText relayouts the text node", async () => {
+    const engine = await getEngine(config);
+    const font = await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"));
+    engine.load_font("Roboto", new Uint8Array(font));
+    engine.upload_scene(JSON.stringify({
+      tag: "view",
+      style: { width: 200, height: 100, flexDirection: "row" },
+      children: [
+        { tag: "text", id: "label", text: "i", style: { fontFamily: "Roboto", fontSize: 20, color: "#ffffff" } },
+        { tag: "view", style: { width: 10, height: 10, backgroundColor: "#ff0000" } },
+      ],
+    }));
+    const before = engine.render_retained(200, 100);
+    patch(engine, { op: "setText", id: "label", text: "wide label" });
+    const after = engine.render_retained(200, 100);
+    const redX = (pixels: Uint8Array) => [...Array(200).keys()].find(x => pixelAt(pixels, x, 5)[0] === 255 && pixelAt(pixels, x, 5)[1] === 0);
+    expect(redX(after)!).toBeGreaterThan(redX(before)! + 40);
+  });
+
+  test("unknown classes are reported with every frame", async () => {
+    const engine = await getEngine(config);
+    engine.upload_scene(JSON.stringify({ tag: "view", id: "root", class: "nope", style: { width: 200, height: 100 } }));
+    engine.render_retained(200, 100);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingClass", name: "nope" }]);
+    engine.render_retained(200, 100);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingClass", name: "nope" }]);
+
+    patch(engine, { op: "insertChild", parent: "root", node: { tag: "view", class: "gone", style: {} } });
+    engine.render_retained(200, 100);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingClass", name: "nope" }, { kind: "missingClass", name: "gone" }]);
+  });
+
+  test("unknown ids are rejected", async () => {
+    const engine = await upload();
+    expect(() => patch(engine, { op: "setText", id: "missing", text: "x" })).toThrow("No node with id 'missing'");
+    expect(() => patch(engine, { op: "removeChild", id: "root" })).toThrow("cannot remove the root node");
+  });
+
+  test("a batch with a failing patch leaves the scene untouched", async () => {
+    const engine = await upload();
+    const before = engine.render_retained(200, 100);
+    expect(() => patch(engine, [
+      { op: "setStyle", id: "a", style: { width: 50, backgroundColor: "#00ff00" } },
+      { op: "insertChild", parent: "root", node: { tag: "view", id: "c", class: "nope", style: { width: 10, height: 10 } } },
+      { op: "removeChild", id: "b" },
+      { op: "setText", id: "missing", text: "x" },
+    ])).toThrow("No node with id 'missing'");
+    expect(engine.render_retained(200, 100)).toEqual(before);
+    expect(JSON.parse(engine.warnings())).toEqual([]);
+    // The ids the batch added or removed are back as they were.
+    expect(() => patch(engine, { op: "removeChild", id: "c" })).toThrow("No node with id 'c'");
+    patch(engine, { op: "removeChild", id: "b" });
+  });
+});
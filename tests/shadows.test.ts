@@ -0,0 +1,103 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { getEngine } from "../lib/wasm.js";
+import { inkBounds } from "./ink.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, x: number, y: number) => pixels[(y * 100 + x) * 4 + 3];
+
+const render = async (child: object) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100 }, children: [child] };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Shadows", () => {
+  test("drop shadows follow the offset and blur", async () => {
+    const pixels = await render({
+      tag: "view",
+      style: { position: "absolute", left: 20, top: 20, width: 40, height: 40, backgroundColor: "#ffffff", shadowColor: "#000000", shadowOffsetX: 10, shadowOffsetY: 10, shadowBlur: 4 },
+    });
+    expect(alphaAt(pixels, 65, 40)).toBeGreaterThan(200);
+    expect(alphaAt(pixels, 15, 40)).toBe(0);
+    // The blur feathers the edge instead of cutting it off.
+    const edge = alphaAt(pixels, 70, 40);
+    expect(edge).toBeGreaterThan(0);
+    expect(edge).toBeLessThan(255);
+  });
+
+  test("box shadows do not show through translucent boxes", async () => {
+    const pixels = await render({
+      tag: "view",
+      style: { position: "absolute", left: 20, top: 20, width: 40, height: 40, backgroundColor: "#ffffff00", shadowColor: "#000000", shadowOffsetX: 5 },
+    });
+    expect(alphaAt(pixels, 40, 40)).toBe(0);
+    expect(alphaAt(pixels, 62, 40)).toBe(255);
+  });
+
+  test("spread grows the shadow shape", async () => {
+    const pixels = await render({
+      tag: "view",
+      style: { position: "absolute", left: 30, top: 30, width: 40, height: 40, backgroundColor: "#ffffff", shadowColor: "#ff0000", shadowSpread: 8 },
+    });
+    expect(alphaAt(pixels, 25, 50)).toBe(255);
+    expect(alphaAt(pixels, 20, 50)).toBe(0);
+  });
+
+  test("rounded corners round the shadow", async () => {
+    const pixels = await render({
+      tag: "view",
+      style: { position: "absolute", left: 30, top: 30, width: 40, height: 40, borderRadius: 20, backgroundColor: "#ffffff", shadowColor: "#ff0000", shadowSpread: 4 },
+    });
+    expect(alphaAt(pixels, 27, 27)).toBe(0);
+    expect(alphaAt(pixels, 27, 50)).toBe(255);
+  });
+
+  test("inset shadows stay inside the node", async () => {
+    const pixels = await render({
+      tag: "view",
+      style: { position: "absolute", left: 20, top: 20, width: 60, height: 60, backgroundColor: "#ffffff", shadowColor: "#000000", shadowBlur: 6, shadowOffsetX: 5, shadowOffsetY: 5, shadowInset: true },
+    });
+    const i = (22 * 100 + 22) * 4;
+    // Top-left edge is darkened, the center is untouched and nothing leaks outside.
+    expect(pixels[i]).toBeLessThan(200);
+    expect(pixels[(50 * 100 + 50) * 4]).toBe(255);
+    expect(alphaAt(pixels, 18, 50)).toBe(0);
+  });
+
+  test("huge shadow blurs render instead of aborting", async () => {
+    for (const shadowInset of [false, true]) {
+      const pixels = await render({
+        tag: "view",
+        style: { position: "absolute", left: 20, top: 20, width: 60, height: 60, backgroundColor: "#ffffff", shadowColor: "#000000", shadowBlur: 1e9, shadowInset },
+      });
+      // Spread that thin, an outer shadow fades to nothing.
+      expect(alphaAt(pixels, 10, 10)).toBe(0);
+      expect(alphaAt(pixels, 50, 50)).toBe(255);
+    }
+  });
+
+  test("text shadows turn with rotated text", async () => {
+    const engine = await getEngine(config);
+    engine.load_font("default", new Uint8Array(await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"))));
+    const scene = {
+      tag: "view",
+      style: { width: 100, height: 100 },
+      children: [{
+        tag: "text",
+        text: "HHHHHH",
+        style: { position: "absolute", left: 20, top: 40, width: 60, height: 20, fontSize: 16, color: "#ffffff", rotate: 90, shadowColor: "#ff0000", shadowOffsetX: 10 },
+      }],
+    };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    // Bounds of the white glyphs and of the red shadow showing past them.
+    const glyphs = inkBounds(pixels, 100, (_r, g) => g > 200);
+    const shadow = inkBounds(pixels, 100, (r, g, _b, a) => a > 100 && r > 200 && g < 50);
+    // The offset runs along the text, which now reads downwards.
+    expect(shadow.left).toBe(glyphs.left);
+    expect(shadow.right).toBe(glyphs.right);
+    expect(shadow.bottom - glyphs.bottom).toBe(10);
+  });
+});
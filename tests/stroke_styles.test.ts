@@ -0,0 +1,48 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { StyleConfig } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, x: number, y: number) => pixels[(y * 100 + x) * 4 + 3];
+
+// A horizontal line from x=10 to x=90, 80px long.
+const renderLine = async (style: StyleConfig) => {
+  const engine = await getEngine(config);
+  const scene = {
+    tag: "view", style: { width: 100, height: 100 },
+    children: [{ tag: "path", d: "M 10 50 L 90 50", style: { position: "absolute", left: 0, top: 0, borderColor: "#ffffff", borderWidth: 4, ...style } }],
+  };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Stroke Styles", () => {
+  test("line caps extend the stroke", async () => {
+    const butt = await renderLine({ borderWidth: 10 });
+    const round = await renderLine({ borderWidth: 10, strokeLineCap: "round" });
+    expect(alphaAt(butt, 7, 50)).toBe(0);
+    expect(alphaAt(round, 7, 50)).toBe(255);
+  });
+
+  test("dash arrays leave gaps", async () => {
+    const pixels = await renderLine({ strokeDashArray: [10, 10] });
+    expect(alphaAt(pixels, 15, 50)).toBe(255);
+    expect(alphaAt(pixels, 25, 50)).toBe(0);
+    expect(alphaAt(pixels, 35, 50)).toBe(255);
+  });
+
+  test("trim keeps the span between start and end", async () => {
+    const pixels = await renderLine({ trimStart: 25, trimEnd: 50 });
+    expect(alphaAt(pixels, 25, 50)).toBe(0);
+    expect(alphaAt(pixels, 35, 50)).toBe(255);
+    expect(alphaAt(pixels, 45, 50)).toBe(255);
+    expect(alphaAt(pixels, 55, 50)).toBe(0);
+  });
+
+  test("trim offset wraps around the end of the path", async () => {
+    const pixels = await renderLine({ trimStart: 0, trimEnd: 25, trimOffset: 90 });
+    expect(alphaAt(pixels, 85, 50)).toBe(255);
+    expect(alphaAt(pixels, 15, 50)).toBe(255);
+    expect(alphaAt(pixels, 50, 50)).toBe(0);
+  });
+});
@@ -0,0 +1,48 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 200, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 200 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const render = async (scene: SceneNode) => {
+  const engine = await getEngine(config);
+  engine.register_styles(JSON.stringify({
+    box: { width: 20, height: 20, backgroundColor: "#ff0000" },
+    accent: { backgroundColor: "#0000ff" },
+  }));
+  return { engine, pixels: engine.render(JSON.stringify(scene), 200, 100) };
+};
+
+describe("Stylesheets", () => {
+  test("classes merge in order, scene styles over registered, inline last", async () => {
+    const { pixels } = await render({
+      tag: "view",
+      style: { width: 200, height: 100 },
+      styles: { wide: { width: 40 }, accent: { backgroundColor: "#00ff00" } },
+      children: [
+        { tag: "view", class: "box" },
+        { tag: "view", class: "box wide" },
+        { tag: "view", class: ["wide", "box"] },
+        { tag: "view", class: "box accent" },
+        { tag: "view", class: "box", style: { backgroundColor: "#ffffff" } },
+      ],
+    });
+    expect(pixelAt(pixels, 5, 5)).toEqual([255, 0, 0, 255]);
+    // "box wide" is 40px wide, ["wide", "box"] only 20px.
+    expect(pixelAt(pixels, 55, 5)).toEqual([255, 0, 0, 255]);
+    expect(pixelAt(pixels, 79, 5)).toEqual([255, 0, 0, 255]);
+    expect(pixelAt(pixels, 85, 5)).toEqual([0, 255, 0, 255]);
+    expect(pixelAt(pixels, 105, 5)).toEqual([255, 255, 255, 255]);
+  });
+
+  test("unknown classes are reported as warnings", async () => {
+    const { engine, pixels } = await render({ tag: "view", style: { width: 200, height: 100 }, children: [{ tag: "view", class: "box nope" }] });
+    expect(pixelAt(pixels, 5, 5)).toEqual([255, 0, 0, 255]);
+    expect(JSON.parse(engine.warnings())).toEqual([{ kind: "missingClass", name: "nope" }]);
+  });
+});
@@ -0,0 +1,45 @@
+import { describe, expect, test } from "bun:test";
+import { renderSingleFrame, measurePath } from "../lib/engine.js";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 40, height: 40, fps: 30, duration: 1 };
+
+const filledPath = (d: string) => (): SceneNode => ({
+  tag: "view",
+  style: { width: 40, height: 40 },
+  children: [{ tag: "path", d, style: { position: "absolute", backgroundColor: "#ff0000" } }]
+});
+
+const alphaAt = (buffer: Uint8Array, x: number, y: number) => buffer[(y * 40 + x) * 4 + 3];
+
+describe("SVG Path Commands", () => {
+  test("arcs are filled", async () => {
+    // Full circle of radius 10 centered at (20, 20) built from two relative arcs
+    const buffer = await renderSingleFrame(filledPath("M 10 20 a 10 10 0 1 0 20 0 a 10 10 0 1 0 -20 0 Z"), config, 0, {});
+    expect(alphaAt(buffer, 20, 20)).toBe(255);
+    expect(alphaAt(buffer, 20, 12)).toBe(255);
+    expect(alphaAt(buffer, 11, 11)).toBe(0);
+  });
+
+  test("H and V lines are filled", async () => {
+    const buffer = await renderSingleFrame(filledPath("M 5 5 H 35 V 35 h -30 Z"), config, 0, {});
+    expect(alphaAt(buffer, 30, 30)).toBe(255);
+    expect(alphaAt(buffer, 2, 2)).toBe(0);
+  });
+
+  test("cubic and smooth curves are filled", async () => {
+    const buffer = await renderSingleFrame(filledPath("M 0 20 C 0 0 20 0 20 20 S 40 40 40 20 L 40 40 L 0 40 Z"), config, 0, {});
+    // The first hump rises above y = 20 near x = 10
+    expect(alphaAt(buffer, 10, 12)).toBe(255);
+    // The second hump dips below y = 20 near x = 30
+    expect(alphaAt(buffer, 30, 24)).toBe(0);
+  });
+
+  test("measurePath agrees with the drawn arc", async () => {
+    await getEngine(config);
+    // Semicircle of radius 10
+    expect(measurePath("M 0 0 A 10 10 0 0 1 20 0")).toBeCloseTo(Math.PI * 10, 0);
+    expect(measurePath("M 0 0 h 10 v 10")).toBeCloseTo(20, 3);
+  });
+});
@@ -0,0 +1,68 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const render = async (rootStyle: StyleConfig, textStyle: StyleConfig = {}) => {
+  const engine = await getEngine(config);
+  const font = await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"));
+  engine.load_font("Roboto", new Uint8Array(font));
+
+  // The text sits two levels below the root, with a marker box after it.
+  const scene: SceneNode = {
+    tag: "view",
+    style: { width: 100, height: 100, flexDirection: "column", fontFamily: "Roboto", ...rootStyle },
+    children: [
+      { tag: "view", style: {}, children: [{ tag: "text", text: "HI", style: textStyle }] },
+      { tag: "view", style: { width: 10, height: 10, backgroundColor: "#00ff00" } },
+    ],
+  };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Text Style Inheritance", () => {
+  test("color and font settings come from ancestors", async () => {
+    const pixels = await render({ color: "#ff0000", fontSize: 20, lineHeight: 30 });
+    const stem = pixelAt(pixels, 2, 10);
+    expect(stem[0]).toBeGreaterThan(200);
+    expect(stem[2]).toBe(0);
+    // The inherited line height sizes the text during layout too.
+    expect(pixelAt(pixels, 5, 29)[1]).toBe(0);
+    expect(pixelAt(pixels, 5, 30)).toEqual([0, 255, 0, 255]);
+  });
+
+  test("a node's own values override inherited ones", async () => {
+    const pixels = await render({ color: "#ff0000", fontSize: 20, lineHeight: 30 }, { color: "#0000ff", lineHeight: 20 });
+    const stem = pixelAt(pixels, 2, 10);
+    expect(stem[2]).toBeGreaterThan(200);
+    expect(stem[0]).toBe(0);
+    expect(pixelAt(pixels, 5, 20)).toEqual([0, 255, 0, 255]);
+  });
+
+  test("mask text inherits from the masked node", async () => {
+    const engine = await getEngine(config);
+    const font = await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"));
+    engine.load_font("Roboto", new Uint8Array(font));
+    const scene: SceneNode = {
+      tag: "view",
+      style: { width: 100, height: 100, fontFamily: "Roboto" },
+      children: [{
+        tag: "view",
+        style: { width: 100, height: 100, backgroundColor: "#ff0000", fontSize: 40, lineHeight: 50 },
+        mask: { tag: "text", text: "HI", style: { color: "#ffffff" } },
+      }],
+    };
+    const pixels = engine.render(JSON.stringify(scene), 100, 100);
+    // The stem of a 40px "H" reaches well below that of the default size.
+    expect(pixelAt(pixels, 5, 37)).toEqual([255, 0, 0, 255]);
+    expect(pixelAt(pixels, 5, 41)[3]).toBe(0);
+  });
+});
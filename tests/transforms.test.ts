@@ -0,0 +1,80 @@
+import { describe, expect, test } from "bun:test";
+import { readFile } from "node:fs/promises";
+import { join } from "node:path";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+import { inkBounds } from "./ink.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, x: number, y: number) => pixels[(y * 100 + x) * 4 + 3];
+
+const render = async (child: Partial<SceneNode> & { style: StyleConfig }) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100 }, children: [{ tag: "view", ...child }] };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+// A 40x40 red square at (20, 20).
+const square: StyleConfig = { position: "absolute", left: 20, top: 20, width: 40, height: 40, backgroundColor: "#ff0000" };
+
+describe("Transforms", () => {
+  test("translate moves the node without moving its layout box", async () => {
+    const pixels = await render({ style: { ...square, translateX: 30 } });
+    expect(alphaAt(pixels, 25, 40)).toBe(0);
+    expect(alphaAt(pixels, 85, 40)).toBe(255);
+  });
+
+  test("scaleX grows from the transform origin", async () => {
+    const pixels = await render({ style: { ...square, scaleX: 2, transformOrigin: "left" } });
+    expect(alphaAt(pixels, 15, 40)).toBe(0);
+    expect(alphaAt(pixels, 95, 40)).toBe(255);
+  });
+
+  test("skewX shears along x", async () => {
+    const pixels = await render({ style: { ...square, skewX: 45, transformOrigin: "top left" } });
+    expect(alphaAt(pixels, 40, 30)).toBe(255);
+    expect(alphaAt(pixels, 25, 50)).toBe(0);
+    expect(alphaAt(pixels, 70, 50)).toBe(255);
+  });
+
+  test("matrix takes CSS order", async () => {
+    const pixels = await render({ style: { ...square, matrix: [1, 0, 0, 1, 30, 0] } });
+    expect(alphaAt(pixels, 25, 40)).toBe(0);
+    expect(alphaAt(pixels, 85, 40)).toBe(255);
+  });
+
+  test("children inherit their parent's transform", async () => {
+    const pixels = await render({
+      style: { position: "absolute", left: 0, top: 0, width: 100, height: 100, scale: 0.5, transformOrigin: "top left" },
+      children: [{ tag: "rect", style: { width: 100, height: 100, backgroundColor: "#00ff00" } }],
+    });
+    expect(alphaAt(pixels, 25, 25)).toBe(255);
+    expect(alphaAt(pixels, 75, 75)).toBe(0);
+  });
+
+  test("masks follow the node's transform", async () => {
+    const pixels = await render({
+      style: { position: "absolute", left: 0, top: 0, width: 100, height: 100, backgroundColor: "#ff0000", scale: 0.5, transformOrigin: "top left" },
+      mask: { tag: "rect", style: { width: 50, height: 100, backgroundColor: "#ffffff" } },
+    });
+    expect(alphaAt(pixels, 15, 25)).toBe(255);
+    expect(alphaAt(pixels, 35, 25)).toBe(0);
+  });
+
+  test("text runs follow the node's rotation", async () => {
+    const engine = await getEngine(config);
+    engine.load_font("default", new Uint8Array(await readFile(join(import.meta.dir, "../assets/Roboto-Regular.ttf"))));
+    const text = (style: StyleConfig) => ({
+      tag: "view",
+      style: { width: 100, height: 100 },
+      children: [{ tag: "text", text: "HHHHHH", style: { position: "absolute", left: 20, top: 40, width: 60, height: 20, fontSize: 16, color: "#ffffff", ...style } }],
+    });
+    const flat = inkBounds(engine.render(JSON.stringify(text({})), 100, 100), 100);
+    expect(flat.width).toBeGreaterThan(flat.height * 3);
+    // Turned a quarter, the run reads downwards instead of across.
+    const turned = inkBounds(engine.render(JSON.stringify(text({ rotate: 90 })), 100, 100), 100);
+    expect(turned.height).toBeGreaterThan(turned.width * 3);
+    expect(Math.abs(turned.height - flat.width)).toBeLessThanOrEqual(1);
+  });
+});
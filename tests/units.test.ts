@@ -0,0 +1,49 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 200, height: 100, fps: 30, duration: 1 };
+
+const alphaAt = (pixels: Uint8Array, width: number, x: number, y: number) => pixels[(y * width + x) * 4 + 3];
+
+const scene: SceneNode = {
+  tag: "view",
+  style: { width: "100%", height: "100%", fontSize: 10 },
+  children: [
+    { tag: "view", style: { position: "absolute", left: "10vw", top: "5vh", width: "50%", height: "2em", backgroundColor: "#ff0000" } },
+    {
+      tag: "view",
+      style: { position: "absolute", left: 0, top: "50%", height: "10px", backgroundColor: "#00ff00" },
+      // A child's own font size wins for its em units.
+      children: [{ tag: "view", style: { width: "3em", height: 5, fontSize: 20 } }],
+    },
+  ],
+};
+
+const render = async (width: number, height: number) => {
+  const engine = await getEngine(config);
+  return engine.render(JSON.stringify(scene), width, height);
+};
+
+describe("Units", () => {
+  test("resolve against a landscape canvas", async () => {
+    const pixels = await render(200, 100);
+    expect(alphaAt(pixels, 200, 19, 10)).toBe(0);
+    expect(alphaAt(pixels, 200, 20, 10)).toBe(255);
+    expect(alphaAt(pixels, 200, 119, 10)).toBe(255);
+    expect(alphaAt(pixels, 200, 120, 10)).toBe(0);
+    expect(alphaAt(pixels, 200, 21, 25)).toBe(0);
+    expect(alphaAt(pixels, 200, 59, 55)).toBe(255);
+    expect(alphaAt(pixels, 200, 61, 55)).toBe(0);
+  });
+
+  test("the same scene follows a portrait canvas", async () => {
+    const pixels = await render(100, 200);
+    expect(alphaAt(pixels, 100, 9, 15)).toBe(0);
+    expect(alphaAt(pixels, 100, 10, 15)).toBe(255);
+    expect(alphaAt(pixels, 100, 59, 15)).toBe(255);
+    expect(alphaAt(pixels, 100, 60, 15)).toBe(0);
+    expect(alphaAt(pixels, 100, 21, 29)).toBe(255);
+    expect(alphaAt(pixels, 100, 59, 105)).toBe(255);
+  });
+});
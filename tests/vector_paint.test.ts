@@ -0,0 +1,53 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+// A square with a square hole, both drawn clockwise.
+const RING = "M 10 10 L 90 10 L 90 90 L 10 90 Z M 30 30 L 70 30 L 70 70 L 30 70 Z";
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const render = async (node: Partial<SceneNode>) => {
+  const engine = await getEngine(config);
+  const scene = {
+    tag: "view", style: { width: 100, height: 100 },
+    children: [{ tag: "path", d: RING, ...node, style: { position: "absolute", left: 0, top: 0, width: 100, height: 100 } }],
+  };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("Vector Paint", () => {
+  test("evenodd leaves holes that nonzero fills", async () => {
+    const evenOdd = await render({ fill: "#ff0000", fillRule: "evenodd" });
+    const nonZero = await render({ fill: "#ff0000" });
+    expect(pixelAt(evenOdd, 20, 20)).toEqual([255, 0, 0, 255]);
+    expect(pixelAt(evenOdd, 50, 50)[3]).toBe(0);
+    expect(pixelAt(nonZero, 50, 50)).toEqual([255, 0, 0, 255]);
+  });
+
+  test("strokes accept gradients", async () => {
+    const pixels = await render({ d: "M 10 50 L 90 50", stroke: { colors: ["#ff0000", "#0000ff"], angle: 90 }, strokeWidth: 6 });
+    const left = pixelAt(pixels, 12, 50);
+    const right = pixelAt(pixels, 88, 50);
+    expect(left[0]).toBeGreaterThan(left[2]);
+    expect(right[2]).toBeGreaterThan(right[0]);
+  });
+
+  test("fill and stroke opacity apply separately", async () => {
+    const pixels = await render({ fill: "#ff0000", fillOpacity: 0.5, stroke: "#00ff00", strokeWidth: 4, strokeOpacity: 0.25 });
+    expect(pixelAt(pixels, 20, 20)[3]).toBe(128);
+    // The stroke's outer half sits alone on the canvas.
+    expect(pixelAt(pixels, 8, 50)[3]).toBe(64);
+  });
+
+  test("a stroke without a width is 1px wide", async () => {
+    const pixels = await render({ d: "M 10 50.5 L 90 50.5", stroke: "#ffffff" });
+    expect(pixelAt(pixels, 50, 50)[3]).toBe(255);
+    expect(pixelAt(pixels, 50, 49)[3]).toBe(0);
+  });
+});
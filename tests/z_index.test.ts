@@ -0,0 +1,60 @@
+import { describe, expect, test } from "bun:test";
+import { getEngine } from "../lib/wasm.js";
+import type { SceneNode, StyleConfig } from "../lib/types.js";
+
+const config = { width: 100, height: 100, fps: 30, duration: 1 };
+
+const pixelAt = (pixels: Uint8Array, x: number, y: number) => {
+  const i = (y * 100 + x) * 4;
+  return Array.from(pixels.slice(i, i + 4));
+};
+
+const RED = [255, 0, 0, 255];
+const GREEN = [0, 255, 0, 255];
+const BLUE = [0, 0, 255, 255];
+const WHITE = [255, 255, 255, 255];
+
+// A 40x40 square at (x, y).
+const square = (backgroundColor: string, x: number, y: number, style: StyleConfig = {}): SceneNode =>
+  ({ tag: "view", style: { position: "absolute", left: x, top: y, width: 40, height: 40, backgroundColor, ...style } });
+
+const full: StyleConfig = { position: "absolute", left: 0, top: 0, width: 100, height: 100 };
+
+const render = async (children: SceneNode[], style: StyleConfig = {}) => {
+  const engine = await getEngine(config);
+  const scene = { tag: "view", style: { width: 100, height: 100, ...style }, children };
+  return engine.render(JSON.stringify(scene), 100, 100);
+};
+
+describe("zIndex", () => {
+  test("siblings paint in zIndex order, ties in tree order", async () => {
+    const pixels = await render([
+      square("#ff0000", 10, 10, { zIndex: 2 }),
+      square("#00ff00", 30, 30),
+      square("#0000ff", 50, 50, { zIndex: 1 }),
+    ]);
+    expect(pixelAt(pixels, 40, 40)).toEqual(RED);
+    expect(pixelAt(pixels, 60, 60)).toEqual(BLUE);
+  });
+
+  test("plain wrappers don't contain zIndex, opacity does", async () => {
+    const pixels = await render([
+      { tag: "view", style: full, children: [square("#ff0000", 10, 10, { zIndex: 1 })] },
+      { tag: "view", style: { ...full, opacity: 0.999 }, children: [square("#0000ff", 50, 50, { zIndex: 5 })] },
+      square("#00ff00", 30, 30),
+      square("#ffffff", 60, 60),
+    ]);
+    expect(pixelAt(pixels, 40, 40)).toEqual(RED);
+    expect(pixelAt(pixels, 55, 55)).toEqual(GREEN);
+    expect(pixelAt(pixels, 65, 65)).toEqual(WHITE);
+  });
+
+  test("negative zIndex goes under siblings but over the context's background", async () => {
+    const pixels = await render(
+      [square("#ff0000", 10, 10, { zIndex: -1 }), square("#00ff00", 30, 30)],
+      { backgroundColor: "#000000" },
+    );
+    expect(pixelAt(pixels, 15, 15)).toEqual(RED);
+    expect(pixelAt(pixels, 40, 40)).toEqual(GREEN);
+  });
+});